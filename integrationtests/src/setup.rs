@@ -3,6 +3,7 @@ use std::time::Duration;
 use crate::{bitcoin_client::BitcoinClient, lnd_client::LndClient};
 use mokshamint::{
     config::{BtcOnchainConfig, DatabaseConfig, ServerConfig},
+    database::postgres::PostgresDB,
     lightning::LightningType,
     mint::MintBuilder,
 };
@@ -57,6 +58,9 @@ pub async fn start_mint(
         ..Default::default()
     };
 
+    let db = PostgresDB::new(&db_config).await?;
+    db.migrate().await;
+
     let mint = MintBuilder::new()
         .with_private_key("my_private_key".to_string())
         .with_server(Some(ServerConfig {
@@ -64,6 +68,7 @@ pub async fn start_mint(
             ..Default::default()
         }))
         .with_db(Some(db_config))
+        .with_database(db)
         .with_lightning(ln)
         .with_btc_onchain(btc_onchain)
         .with_fee(Some((0.0, 0).into()))
@@ -5,6 +5,7 @@ use moksha_core::primitives::{
     CurrencyUnit, MeltBtcOnchainState, PaymentMethod, PostMeltBtcOnchainResponse,
     PostMintQuoteBolt11Response, PostMintQuoteBtcOnchainResponse,
 };
+use moksha_core::subscription::SubscriptionKind;
 use moksha_core::token::TokenV3;
 use moksha_wallet::client::CashuClient;
 
@@ -56,6 +57,19 @@ enum Command {
 
     /// Add a new mint to the wallet
     AddMint { mint_url: Url },
+
+    /// Restore tokens from seed words
+    Restore {
+        seed_words: String,
+
+        /// mint to add before restoring. Tokens are restored from all mints in the wallet
+        #[clap(short, long)]
+        mint_url: Option<Url>,
+
+        /// replace the seed of a wallet that already has tokens or used secrets of another seed
+        #[clap(long)]
+        overwrite_seed: bool,
+    },
 }
 
 #[tokio::main]
@@ -76,7 +90,7 @@ async fn main() -> anyhow::Result<()> {
     let term = Term::stdout();
     let localstore = SqliteLocalStore::with_path(db_path.clone()).await?;
     let client = CrossPlatformHttpClient::new();
-    let wallet = moksha_wallet::wallet::WalletBuilder::default()
+    let mut wallet = moksha_wallet::wallet::WalletBuilder::default()
         .with_client(client)
         .with_localstore(localstore)
         .build()
@@ -98,6 +112,31 @@ async fn main() -> anyhow::Result<()> {
             wallet.add_mint_keysets(&mint_url).await?;
             term.write_line("Mint added successfully ")?;
         }
+        Command::Restore {
+            seed_words,
+            mint_url,
+            overwrite_seed,
+        } => {
+            if let Some(mint_url) = mint_url {
+                if !wallet.get_mint_urls().await?.contains(&mint_url) {
+                    wallet.add_mint_keysets(&mint_url).await?;
+                }
+            }
+
+            if wallet.get_mint_urls().await?.is_empty() {
+                term.write_line("Error: No mints found. Add a mint with --mint-url")?;
+                return Ok(());
+            }
+
+            let progress_bar = cli::progress_bar()?;
+            progress_bar.set_message("Restoring tokens ...");
+            let restored_amount = wallet.restore(&seed_words, overwrite_seed).await?;
+            progress_bar.finish_with_message(format!(
+                "Restored {} (sat)\n",
+                restored_amount.to_formatted_string(&Locale::en)
+            ));
+            cli::show_total_balance(&wallet).await?;
+        }
         Command::Info => {
             let wallet_version = style(env!("CARGO_PKG_VERSION")).cyan();
             let mint_urls = wallet.get_mint_urls().await?;
@@ -412,20 +451,50 @@ async fn main() -> anyhow::Result<()> {
                 .get_active(&mint_url, &currency)
                 .expect("Keyset not found");
 
+            // the mint pushes the state of bolt11 quotes if it supports subscriptions, otherwise the quote is polled
+            let subscription_supported = payment_method == PaymentMethod::Bolt11
+                && info.nuts.nut17.as_ref().is_some_and(|nut17| {
+                    nut17.supports(
+                        SubscriptionKind::Bolt11MintQuote,
+                        &payment_method,
+                        &currency,
+                    )
+                });
+
             let progress_bar = cli::progress_bar()?;
             progress_bar.set_message("Waiting for payment ...");
 
             loop {
-                tokio::time::sleep_until(
-                    tokio::time::Instant::now() + std::time::Duration::from_millis(500),
-                )
-                .await;
-
-                if !wallet
-                    .is_quote_paid(&mint_url, &payment_method, quote.clone())
-                    .await?
-                {
-                    continue;
+                if subscription_supported {
+                    // the quote is polled as well if the mint doesn't push the payment in time
+                    let notified = tokio::time::timeout(
+                        std::time::Duration::from_secs(10),
+                        wallet.wait_for_mint_quote_bolt11_paid(&mint_url, quote.clone()),
+                    )
+                    .await;
+                    match notified {
+                        Ok(result) => result?,
+                        Err(_) => {
+                            if !wallet
+                                .is_quote_paid(&mint_url, &payment_method, quote.clone())
+                                .await?
+                            {
+                                continue;
+                            }
+                        }
+                    }
+                } else {
+                    tokio::time::sleep_until(
+                        tokio::time::Instant::now() + std::time::Duration::from_millis(500),
+                    )
+                    .await;
+
+                    if !wallet
+                        .is_quote_paid(&mint_url, &payment_method, quote.clone())
+                        .await?
+                    {
+                        continue;
+                    }
                 }
 
                 // FIXME store quote in db and add option to retry minting later
@@ -2,8 +2,12 @@
 //!
 //! The `BlindedMessage` struct represents a blinded message, with an `amount` field for the amount in satoshis and a `b_` field for the public key of the blinding factor.
 //!
+//! The optional `witness` field of a `BlindedMessage` contains the signatures on `B_` that are required for inputs locked with the `SIG_ALL` flag as described in [Nut-11](https://github.com/cashubtc/nuts/blob/main/11.md)
+//!
 //! The `BlindedSignature` struct represents a blinded signature, with an `amount` field for the amount in satoshis, a `c_` field for the public key of the blinding factor, and an optional `id` field for the ID of the signature.
 //!
+//! The `DleqProof` struct represents the DLEQ proof (e, s) of a blinded signature as described in [Nut-12](https://github.com/cashubtc/nuts/blob/main/12.md)
+//!
 //! Both the `BlindedMessage` and `BlindedSignature` structs are serializable and deserializable using serde.
 //!
 //! The `TotalAmount` trait is also defined in this module, which provides a `total_amount` method for calculating the total amount of a vector of `BlindedMessage` or `BlindedSignature` structs. The trait is implemented for both `Vec<BlindedMessage>` and `Vec<BlindedSignature>`.
@@ -21,6 +25,16 @@ pub struct BlindedSignature {
     #[schema(value_type=String)]
     pub c_: PublicKey,
     pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dleq: Option<DleqProof>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct DleqProof {
+    #[schema(value_type=String)]
+    pub e: SecretKey,
+    #[schema(value_type=String)]
+    pub s: SecretKey,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -31,6 +45,8 @@ pub struct BlindedMessage {
     pub b_: PublicKey,
     // FIXME use KeysetId
     pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub witness: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -40,8 +40,32 @@
 //!```
 //! If true, C must have originated from Bob
 //!
+//! DLEQ proofs as described in [Nut-12](https://github.com/cashubtc/nuts/blob/main/12.md) allow Alice (or any
+//! later holder of the token) to check that C' was signed with the same private key a as the public key A, without
+//! contacting Bob:
 //!
-use crate::{blind::BlindingFactor, error::MokshaCoreError};
+//! Bob:
+//!```python
+//! r = random nonce
+//! R1 = r*G
+//! R2 = r*B'
+//! e = hash(R1,R2,A,C')
+//! s = r + e*a
+//! return e, s
+//!```
+//!
+//! Alice / Carol:
+//!```python
+//! R1 = s*G - e*A
+//! R2 = s*B'- e*C'
+//! e == hash(R1,R2,A,C')
+//!```
+//!
+use crate::{
+    blind::{BlindingFactor, DleqProof},
+    error::MokshaCoreError,
+    proof::Proof,
+};
 use bitcoin_hashes::{sha256, Hash};
 use secp256k1::{All, PublicKey, Scalar, Secp256k1, SecretKey};
 use std::iter::once;
@@ -131,6 +155,78 @@ impl Dhke {
             MokshaCoreError::Secp256k1Error(secp256k1::Error::InvalidPublicKey),
         )
     }
+
+    /// Signs the blinded message B' like step2_bob and creates a DLEQ proof that C' was signed with the private key a
+    pub fn step2_bob_dleq(
+        &self,
+        b_: PublicKey,
+        a: &SecretKey,
+    ) -> Result<(PublicKey, DleqProof), MokshaCoreError> {
+        let c_ = self.step2_bob(b_, a)?;
+
+        let r = SecretKey::new(&mut secp256k1::rand::thread_rng());
+        let r1 = PublicKey::from_secret_key(&self.secp, &r);
+        let r2 = b_.mul_tweak(&self.secp, &Scalar::from(r))?;
+        let e = SecretKey::from_slice(&hash_e(&[
+            r1,
+            r2,
+            PublicKey::from_secret_key(&self.secp, a),
+            c_,
+        ]))?;
+        let s = r.add_tweak(&Scalar::from(e.mul_tweak(&Scalar::from(*a))?))?;
+
+        Ok((c_, DleqProof { e, s }))
+    }
+
+    /// Verifies the DLEQ proof of a blinded signature C' for the blinded message B' and the mint public key A
+    pub fn verify_dleq(
+        &self,
+        dleq: &DleqProof,
+        a: PublicKey,
+        b_: PublicKey,
+        c_: PublicKey,
+    ) -> Result<bool, MokshaCoreError> {
+        let e = Scalar::from(dleq.e);
+        let r1 = PublicKey::from_secret_key(&self.secp, &dleq.s)
+            .combine(&a.mul_tweak(&self.secp, &e)?.negate(&self.secp))?;
+        let r2 = b_
+            .mul_tweak(&self.secp, &Scalar::from(dleq.s))?
+            .combine(&c_.mul_tweak(&self.secp, &e)?.negate(&self.secp))?;
+
+        Ok(hash_e(&[r1, r2, a, c_]) == dleq.e.secret_bytes())
+    }
+
+    /// Verifies the DLEQ proof of an unblinded proof. B' and C' are reconstructed from the blinding factor r
+    /// that is included in the proof. Returns false if the proof doesn't contain a DLEQ proof.
+    pub fn verify_proof_dleq(&self, proof: &Proof, a: PublicKey) -> Result<bool, MokshaCoreError> {
+        let Some(dleq) = &proof.dleq else {
+            return Ok(false);
+        };
+
+        let b_ = self.step1_alice(proof.secret.clone(), &dleq.r.into())?;
+        let c_ = proof
+            .c
+            .combine(&a.mul_tweak(&self.secp, &Scalar::from(dleq.r))?)?;
+
+        self.verify_dleq(
+            &DleqProof {
+                e: dleq.e,
+                s: dleq.s,
+            },
+            a,
+            b_,
+            c_,
+        )
+    }
+}
+
+/// Hashes the uncompressed hex representation of the given public keys as used by [Nut-12](https://github.com/cashubtc/nuts/blob/main/12.md)
+fn hash_e(public_keys: &[PublicKey]) -> [u8; 32] {
+    let e = public_keys
+        .iter()
+        .map(|pk| hex::encode(pk.serialize_uncompressed()))
+        .collect::<String>();
+    sha256::Hash::hash(e.as_bytes()).to_byte_array()
 }
 
 pub fn public_key_from_hex(hex: &str) -> secp256k1::PublicKey {
@@ -144,8 +240,9 @@ mod tests {
     use std::str::FromStr;
 
     use crate::{
-        blind::BlindingFactor,
-        dhke::{public_key_from_hex, Dhke},
+        blind::{BlindingFactor, DleqProof},
+        dhke::{hash_e, public_key_from_hex, Dhke},
+        proof::Proof,
     };
     use anyhow::Ok;
     use pretty_assertions::assert_eq;
@@ -287,4 +384,85 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_hash_e() -> anyhow::Result<()> {
+        let r1 = public_key_from_hex(
+            "020000000000000000000000000000000000000000000000000000000000000001",
+        );
+        let c_ = public_key_from_hex(
+            "02a9acc1e48c25eeeb9289b5031cc57da9fe72f3fe2861d264bdc074209b107ba2",
+        );
+
+        let e = hash_e(&[r1, r1, r1, c_]);
+        assert_eq!(
+            "a4dc034b74338c28c6bc3ea49731f2a24440fc7c4affc08b31a93fc9fbe6401e",
+            hex::encode(e)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_dleq() -> anyhow::Result<()> {
+        let dhke = Dhke::new();
+        let a = public_key_from_hex(
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        );
+        let b_ = public_key_from_hex(
+            "02a9acc1e48c25eeeb9289b5031cc57da9fe72f3fe2861d264bdc074209b107ba2",
+        );
+        let c_ = public_key_from_hex(
+            "02a9acc1e48c25eeeb9289b5031cc57da9fe72f3fe2861d264bdc074209b107ba2",
+        );
+        let dleq = DleqProof {
+            e: pk_from_hex("9818e061ee51d5c8edc3342369a554998ff7b4381c8652d724cdf46429be73d9"),
+            s: pk_from_hex("9818e061ee51d5c8edc3342369a554998ff7b4381c8652d724cdf46429be73da"),
+        };
+
+        assert!(dhke.verify_dleq(&dleq, a, b_, c_)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_proof_dleq() -> anyhow::Result<()> {
+        let dhke = Dhke::new();
+        let proof: Proof = serde_json::from_str(
+            r#"{"amount":1,"id":"00882760bfa2eb41","secret":"daf4dd00a2b68a0858a80450f52c8a7d2ccf87d375e43e216e0c571f089f63e9","C":"024369d2d22a80ecf78f3937da9d5f30c1b9f74f0c32684d583cca0fa6a61cdcfc","dleq":{"e":"b31e58ac6527f34975ffab13e70a48b6d2b0d35abc4b03f0151f09ee1a9763d4","s":"8fbae004c59e754d71df67e392b6ae4e29293113ddc2ec86592a0431d16306d8","r":"a6d13fcd7a18442e6076f5e1e7c887ad5de40a019824bdfa9fe740d302e8d861"}}"#,
+        )?;
+        let a = public_key_from_hex(
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        );
+
+        assert!(dhke.verify_proof_dleq(&proof, a)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_step2_bob_dleq() -> anyhow::Result<()> {
+        let dhke = Dhke::new();
+        let a = pk_from_hex("0000000000000000000000000000000000000000000000000000000000000001");
+        let r: BlindingFactor =
+            "0000000000000000000000000000000000000000000000000000000000000002".try_into()?;
+
+        let b_ = dhke.step1_alice("test_message", &r)?;
+        let (c_, dleq) = dhke.step2_bob_dleq(b_, &a)?;
+        assert_eq!(c_, dhke.step2_bob(b_, &a)?);
+        assert!(dhke.verify_dleq(&dleq, a.public_key(&dhke.secp), b_, c_)?);
+
+        // a DLEQ proof for a different key must not be valid
+        let other = pk_from_hex("0000000000000000000000000000000000000000000000000000000000000003");
+        assert!(!dhke.verify_dleq(&dleq, other.public_key(&dhke.secp), b_, c_)?);
+
+        // Carol can verify the unblinded proof with the blinding factor
+        let c = dhke.step3_alice(c_, r.clone(), a.public_key(&dhke.secp))?;
+        let proof = Proof::new(1, "test_message".to_owned(), c, "someid".to_owned()).with_dleq(
+            crate::proof::ProofDleq {
+                e: dleq.e,
+                s: dleq.s,
+                r: r.to_secret_key(),
+            },
+        );
+        assert!(dhke.verify_proof_dleq(&proof, a.public_key(&dhke.secp))?);
+        Ok(())
+    }
 }
@@ -37,4 +37,13 @@ pub enum MokshaCoreError {
 
     #[error("Invalid Keyset-ID")]
     Slice(#[from] std::array::TryFromSliceError),
+
+    #[error("Invalid secret {0}")]
+    InvalidSecret(String),
+
+    #[error("Invalid witness")]
+    InvalidWitness,
+
+    #[error("Spending condition not met: {0}")]
+    SpendingConditionNotMet(String),
 }
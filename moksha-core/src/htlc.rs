@@ -0,0 +1,278 @@
+//! This module defines the hashed time-lock spending condition as described in [Nut-14](https://github.com/cashubtc/nuts/blob/main/14.md)
+//!
+//! A HTLC locked proof uses a well-known secret (see [Nut-10](https://github.com/cashubtc/nuts/blob/main/10.md)) of kind `HTLC`. The `data` field
+//! contains the hex encoded sha256 hash of the preimage and the tags can contain `pubkeys` and `n_sigs` that have to sign in addition to the preimage,
+//! as well as a `locktime` and `refund` keys like in [Nut-11](https://github.com/cashubtc/nuts/blob/main/11.md).
+//!
+//! The `HTLCConditions` struct represents the conditions of such a secret and can verify a `HTLCWitness`, which contains the preimage and optional signatures on the secret.
+
+use std::{fmt::Display, str::FromStr};
+
+use bitcoin_hashes::{sha256, Hash};
+use secp256k1::{PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::MokshaCoreError,
+    p2pk::{
+        count_valid_signatures, parse_keys_tag, parse_number_tag, parse_signatures, sign_message,
+        tag_with_keys,
+    },
+    proof::Proof,
+    secret::{SecretKind, WellKnownSecret},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HTLCConditions {
+    pub hash: sha256::Hash,
+    /// if not empty, `n_sigs` of these keys have to sign in addition to the preimage
+    pub pubkeys: Vec<PublicKey>,
+    pub n_sigs: Option<u64>,
+    /// unix timestamp in seconds after which the refund keys can spend the proof
+    pub locktime: Option<u64>,
+    pub refund_keys: Vec<PublicKey>,
+}
+
+impl HTLCConditions {
+    pub const fn new(hash: sha256::Hash) -> Self {
+        Self {
+            hash,
+            pubkeys: vec![],
+            n_sigs: None,
+            locktime: None,
+            refund_keys: vec![],
+        }
+    }
+
+    /// Creates conditions for the sha256 hash of the given preimage
+    pub fn from_preimage(preimage: &[u8]) -> Self {
+        Self::new(sha256::Hash::hash(preimage))
+    }
+
+    pub fn with_pubkeys(mut self, pubkeys: Vec<PublicKey>, n_sigs: u64) -> Self {
+        self.pubkeys = pubkeys;
+        self.n_sigs = Some(n_sigs);
+        self
+    }
+
+    pub fn with_locktime(mut self, locktime: u64, refund_keys: Vec<PublicKey>) -> Self {
+        self.locktime = Some(locktime);
+        self.refund_keys = refund_keys;
+        self
+    }
+
+    /// Creates a new well-known secret with a random nonce for these conditions
+    pub fn to_secret(&self) -> WellKnownSecret {
+        let mut tags = vec![];
+        if !self.pubkeys.is_empty() {
+            tags.push(tag_with_keys("pubkeys", &self.pubkeys));
+        }
+        if let Some(n_sigs) = self.n_sigs {
+            tags.push(vec!["n_sigs".to_owned(), n_sigs.to_string()]);
+        }
+        if let Some(locktime) = self.locktime {
+            tags.push(vec!["locktime".to_owned(), locktime.to_string()]);
+        }
+        if !self.refund_keys.is_empty() {
+            tags.push(tag_with_keys("refund", &self.refund_keys));
+        }
+        WellKnownSecret::new(SecretKind::HTLC, self.hash.to_string(), tags)
+    }
+
+    /// Verifies the witness of a proof against these conditions.
+    ///
+    /// Before the locktime (or without a locktime) the witness must contain the preimage of the hash and, if `pubkeys` are set, signatures of `n_sigs` distinct keys.
+    /// After the locktime the proof can additionally be spent with a signature of one of the refund keys, or by anyone if there are no refund keys.
+    pub fn verify(
+        &self,
+        msg: &[u8],
+        witness: Option<&str>,
+        now: u64,
+    ) -> Result<(), MokshaCoreError> {
+        let witness = witness
+            .map(|witness| witness.parse::<HTLCWitness>())
+            .transpose()?
+            .unwrap_or_default();
+        let signatures = parse_signatures(&witness.signatures)?;
+
+        let preimage_error = match hex::decode(&witness.preimage) {
+            Ok(preimage) if sha256::Hash::hash(&preimage) == self.hash => {
+                let required = self.n_sigs.unwrap_or(1);
+                if self.pubkeys.is_empty()
+                    || count_valid_signatures(msg, &signatures, &self.pubkeys) >= required
+                {
+                    return Ok(());
+                }
+                format!("{required} valid signatures required")
+            }
+            _ => "invalid preimage".to_owned(),
+        };
+
+        if self.locktime.is_some_and(|locktime| now >= locktime) {
+            if self.refund_keys.is_empty()
+                || count_valid_signatures(msg, &signatures, &self.refund_keys) >= 1
+            {
+                return Ok(());
+            }
+            return Err(MokshaCoreError::SpendingConditionNotMet(
+                "no valid refund signature".to_owned(),
+            ));
+        }
+
+        Err(MokshaCoreError::SpendingConditionNotMet(preimage_error))
+    }
+}
+
+impl TryFrom<&WellKnownSecret> for HTLCConditions {
+    type Error = MokshaCoreError;
+
+    fn try_from(secret: &WellKnownSecret) -> Result<Self, Self::Error> {
+        if secret.kind != SecretKind::HTLC {
+            return Err(MokshaCoreError::InvalidSecret(
+                "secret is not of kind HTLC".to_owned(),
+            ));
+        }
+        Ok(Self {
+            hash: sha256::Hash::from_str(&secret.data).map_err(|_| {
+                MokshaCoreError::InvalidSecret(format!("invalid hash {}", secret.data))
+            })?,
+            pubkeys: parse_keys_tag(secret, "pubkeys")?,
+            n_sigs: parse_number_tag(secret, "n_sigs")?,
+            locktime: parse_number_tag(secret, "locktime")?,
+            refund_keys: parse_keys_tag(secret, "refund")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HTLCWitness {
+    /// hex encoded preimage, empty if the proof is spent with a refund signature
+    #[serde(default)]
+    pub preimage: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<String>,
+}
+
+impl FromStr for HTLCWitness {
+    type Err = MokshaCoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map_err(|_| MokshaCoreError::InvalidWitness)
+    }
+}
+
+impl Display for HTLCWitness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{json}")
+    }
+}
+
+impl Proof {
+    /// Sets the witness of a HTLC locked proof to the given hex encoded preimage and optionally signs the secret
+    pub fn add_htlc_witness(&mut self, preimage: &str, secret_key: Option<&SecretKey>) {
+        self.witness = Some(
+            HTLCWitness {
+                preimage: preimage.to_owned(),
+                signatures: secret_key
+                    .map(|key| vec![sign_message(key, self.secret.as_bytes()).to_string()])
+                    .unwrap_or_default(),
+            }
+            .to_string(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+    use crate::{
+        error::MokshaCoreError, htlc::HTLCConditions, proof::Proof, secret::WellKnownSecret,
+    };
+
+    const PREIMAGE: [u8; 32] = [42; 32];
+
+    fn keypair(byte: u8) -> (SecretKey, PublicKey) {
+        let secret_key = SecretKey::from_slice(&[byte; 32]).expect("invalid secret key");
+        (secret_key, secret_key.public_key(&Secp256k1::new()))
+    }
+
+    fn locked_proof(conditions: &HTLCConditions) -> Proof {
+        let (_, c) = keypair(9);
+        Proof::new(
+            8,
+            conditions.to_secret().to_string(),
+            c,
+            "00f4683f9caf8793".to_owned(),
+        )
+    }
+
+    fn verify(proof: &Proof, now: u64) -> Result<(), MokshaCoreError> {
+        let secret =
+            WellKnownSecret::from_secret(&proof.secret)?.expect("secret is not well-known");
+        HTLCConditions::try_from(&secret)?.verify(
+            proof.secret.as_bytes(),
+            proof.witness.as_deref(),
+            now,
+        )
+    }
+
+    #[test]
+    fn test_conditions_roundtrip() -> anyhow::Result<()> {
+        let (_, pubkey) = keypair(1);
+        let (_, refund) = keypair(3);
+        let conditions = HTLCConditions::from_preimage(&PREIMAGE)
+            .with_pubkeys(vec![pubkey], 1)
+            .with_locktime(1_700_000_000, vec![refund]);
+
+        let secret: WellKnownSecret = conditions.to_secret().to_string().parse()?;
+        assert!(secret.to_string().starts_with(r#"["HTLC",{"nonce":""#));
+        assert_eq!(conditions, HTLCConditions::try_from(&secret)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_preimage() -> anyhow::Result<()> {
+        let mut proof = locked_proof(&HTLCConditions::from_preimage(&PREIMAGE));
+        assert!(verify(&proof, 0).is_err());
+
+        proof.add_htlc_witness(&hex::encode([1; 32]), None);
+        assert!(verify(&proof, 0).is_err());
+
+        proof.add_htlc_witness(&hex::encode(PREIMAGE), None);
+        assert!(verify(&proof, 0).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_preimage_and_signature() -> anyhow::Result<()> {
+        let (secret_key, pubkey) = keypair(1);
+        let (wrong_key, _) = keypair(2);
+        let mut proof =
+            locked_proof(&HTLCConditions::from_preimage(&PREIMAGE).with_pubkeys(vec![pubkey], 1));
+
+        proof.add_htlc_witness(&hex::encode(PREIMAGE), None);
+        assert!(verify(&proof, 0).is_err());
+
+        proof.add_htlc_witness(&hex::encode(PREIMAGE), Some(&wrong_key));
+        assert!(verify(&proof, 0).is_err());
+
+        proof.add_htlc_witness(&hex::encode(PREIMAGE), Some(&secret_key));
+        assert!(verify(&proof, 0).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_refund() -> anyhow::Result<()> {
+        let (refund_key, refund_pubkey) = keypair(3);
+        let mut proof = locked_proof(
+            &HTLCConditions::from_preimage(&PREIMAGE).with_locktime(100, vec![refund_pubkey]),
+        );
+
+        proof.add_htlc_witness("", Some(&refund_key));
+        assert!(verify(&proof, 99).is_err());
+        assert!(verify(&proof, 100).is_ok());
+        Ok(())
+    }
+}
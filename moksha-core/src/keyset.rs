@@ -57,12 +57,20 @@ pub struct Keyset {
     pub id: String, // FIXME use KeysetId
     pub unit: CurrencyUnit,
     pub active: bool,
+    /// fee in parts per thousand units for every input of this keyset (NUT-02)
+    #[serde(default)]
+    pub input_fee_ppk: u64,
 }
 
 impl Keysets {
     pub fn new(id: String, unit: CurrencyUnit, active: bool) -> Self {
         Self {
-            keysets: vec![Keyset { id, unit, active }],
+            keysets: vec![Keyset {
+                id,
+                unit,
+                active,
+                input_fee_ppk: 0,
+            }],
         }
     }
 
@@ -3,7 +3,11 @@ pub mod blind;
 pub mod dhke;
 pub mod error;
 pub mod fixture;
+pub mod htlc;
 pub mod keyset;
+pub mod p2pk;
 pub mod primitives;
 pub mod proof;
+pub mod secret;
+pub mod subscription;
 pub mod token;
@@ -0,0 +1,417 @@
+//! This module defines the Pay-to-Public-Key spending condition as described in [Nut-11](https://github.com/cashubtc/nuts/blob/main/11.md)
+//!
+//! A P2PK locked proof uses a well-known secret (see [Nut-10](https://github.com/cashubtc/nuts/blob/main/10.md)) of kind `P2PK`. The `data` field
+//! contains the public key the proof is locked to and the tags contain additional conditions like `sigflag`, `n_sigs`, `pubkeys`, `locktime` and `refund`.
+//!
+//! The `P2PKConditions` struct represents the conditions of such a secret and can verify the signatures of a `P2PKWitness`.
+//! Signatures are schnorr signatures on the sha256 hash of the message. The message is the secret for proofs and the `B_` for blinded messages.
+
+use std::{fmt::Display, str::FromStr};
+
+use bitcoin_hashes::{sha256, Hash};
+use secp256k1::{schnorr::Signature, Keypair, Message, PublicKey, Secp256k1, SecretKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    blind::BlindedMessage,
+    error::MokshaCoreError,
+    proof::Proof,
+    secret::{SecretKind, WellKnownSecret},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SigFlag {
+    #[default]
+    #[serde(rename = "SIG_INPUTS")]
+    SigInputs,
+    #[serde(rename = "SIG_ALL")]
+    SigAll,
+}
+
+impl Display for SigFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SigInputs => write!(f, "SIG_INPUTS"),
+            Self::SigAll => write!(f, "SIG_ALL"),
+        }
+    }
+}
+
+impl FromStr for SigFlag {
+    type Err = MokshaCoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "SIG_INPUTS" => Ok(Self::SigInputs),
+            "SIG_ALL" => Ok(Self::SigAll),
+            _ => Err(MokshaCoreError::InvalidSecret(format!(
+                "unknown sigflag {s}"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct P2PKConditions {
+    pub pubkey: PublicKey,
+    pub additional_pubkeys: Vec<PublicKey>,
+    pub n_sigs: Option<u64>,
+    pub sig_flag: SigFlag,
+    /// unix timestamp in seconds after which the refund keys can spend the proof
+    pub locktime: Option<u64>,
+    pub refund_keys: Vec<PublicKey>,
+}
+
+impl P2PKConditions {
+    pub fn new(pubkey: PublicKey) -> Self {
+        Self {
+            pubkey,
+            additional_pubkeys: vec![],
+            n_sigs: None,
+            sig_flag: SigFlag::default(),
+            locktime: None,
+            refund_keys: vec![],
+        }
+    }
+
+    pub fn with_additional_pubkeys(mut self, pubkeys: Vec<PublicKey>, n_sigs: u64) -> Self {
+        self.additional_pubkeys = pubkeys;
+        self.n_sigs = Some(n_sigs);
+        self
+    }
+
+    pub const fn with_sig_flag(mut self, sig_flag: SigFlag) -> Self {
+        self.sig_flag = sig_flag;
+        self
+    }
+
+    pub fn with_locktime(mut self, locktime: u64, refund_keys: Vec<PublicKey>) -> Self {
+        self.locktime = Some(locktime);
+        self.refund_keys = refund_keys;
+        self
+    }
+
+    /// Creates a new well-known secret with a random nonce for these conditions
+    pub fn to_secret(&self) -> WellKnownSecret {
+        let mut tags = vec![vec!["sigflag".to_owned(), self.sig_flag.to_string()]];
+        if let Some(n_sigs) = self.n_sigs {
+            tags.push(vec!["n_sigs".to_owned(), n_sigs.to_string()]);
+        }
+        if !self.additional_pubkeys.is_empty() {
+            tags.push(tag_with_keys("pubkeys", &self.additional_pubkeys));
+        }
+        if let Some(locktime) = self.locktime {
+            tags.push(vec!["locktime".to_owned(), locktime.to_string()]);
+        }
+        if !self.refund_keys.is_empty() {
+            tags.push(tag_with_keys("refund", &self.refund_keys));
+        }
+        WellKnownSecret::new(SecretKind::P2PK, self.pubkey.to_string(), tags)
+    }
+
+    /// Verifies the witness of a proof or blinded message against these conditions.
+    ///
+    /// Before the locktime (or without a locktime) at least `n_sigs` distinct keys of `pubkey` and `pubkeys` have to sign the message.
+    /// After the locktime the proof can additionally be spent with a signature of one of the refund keys, or by anyone if there are no refund keys.
+    pub fn verify(
+        &self,
+        msg: &[u8],
+        witness: Option<&str>,
+        now: u64,
+    ) -> Result<(), MokshaCoreError> {
+        let signatures = match witness {
+            Some(witness) => witness.parse::<P2PKWitness>()?.signatures()?,
+            None => vec![],
+        };
+
+        let mut keys = vec![self.pubkey];
+        keys.extend(self.additional_pubkeys.iter());
+        let required = self.n_sigs.unwrap_or(1);
+        if count_valid_signatures(msg, &signatures, &keys) >= required {
+            return Ok(());
+        }
+
+        if self.locktime.is_some_and(|locktime| now >= locktime) {
+            if self.refund_keys.is_empty()
+                || count_valid_signatures(msg, &signatures, &self.refund_keys) >= 1
+            {
+                return Ok(());
+            }
+            return Err(MokshaCoreError::SpendingConditionNotMet(
+                "no valid refund signature".to_owned(),
+            ));
+        }
+
+        Err(MokshaCoreError::SpendingConditionNotMet(format!(
+            "{required} valid signatures required"
+        )))
+    }
+}
+
+impl TryFrom<&WellKnownSecret> for P2PKConditions {
+    type Error = MokshaCoreError;
+
+    fn try_from(secret: &WellKnownSecret) -> Result<Self, Self::Error> {
+        if secret.kind != SecretKind::P2PK {
+            return Err(MokshaCoreError::InvalidSecret(
+                "secret is not of kind P2PK".to_owned(),
+            ));
+        }
+        Ok(Self {
+            pubkey: parse_pubkey(&secret.data)?,
+            additional_pubkeys: parse_keys_tag(secret, "pubkeys")?,
+            n_sigs: parse_number_tag(secret, "n_sigs")?,
+            sig_flag: secret
+                .tag("sigflag")
+                .and_then(|values| values.first())
+                .map(|flag| flag.parse())
+                .transpose()?
+                .unwrap_or_default(),
+            locktime: parse_number_tag(secret, "locktime")?,
+            refund_keys: parse_keys_tag(secret, "refund")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct P2PKWitness {
+    pub signatures: Vec<String>,
+}
+
+impl P2PKWitness {
+    fn signatures(&self) -> Result<Vec<Signature>, MokshaCoreError> {
+        parse_signatures(&self.signatures)
+    }
+}
+
+impl FromStr for P2PKWitness {
+    type Err = MokshaCoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map_err(|_| MokshaCoreError::InvalidWitness)
+    }
+}
+
+impl Display for P2PKWitness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{json}")
+    }
+}
+
+/// Creates a schnorr signature on the sha256 hash of the message
+pub fn sign_message(secret_key: &SecretKey, msg: &[u8]) -> Signature {
+    let secp = Secp256k1::new();
+    let keypair = Keypair::from_secret_key(&secp, secret_key);
+    secp.sign_schnorr(&hash_message(msg), &keypair)
+}
+
+/// Adds a signature on the message to an existing witness
+fn add_signature(
+    witness: Option<&str>,
+    secret_key: &SecretKey,
+    msg: &[u8],
+) -> Result<String, MokshaCoreError> {
+    let mut witness = match witness {
+        Some(witness) => witness.parse::<P2PKWitness>()?,
+        None => P2PKWitness::default(),
+    };
+    witness
+        .signatures
+        .push(sign_message(secret_key, msg).to_string());
+    Ok(witness.to_string())
+}
+
+impl Proof {
+    /// Signs the secret of the proof and adds the signature to the witness
+    pub fn sign_p2pk(&mut self, secret_key: &SecretKey) -> Result<(), MokshaCoreError> {
+        self.witness = Some(add_signature(
+            self.witness.as_deref(),
+            secret_key,
+            self.secret.as_bytes(),
+        )?);
+        Ok(())
+    }
+}
+
+impl BlindedMessage {
+    /// Signs `B_` of the blinded message and adds the signature to the witness. Only required for `SIG_ALL`.
+    pub fn sign_p2pk(&mut self, secret_key: &SecretKey) -> Result<(), MokshaCoreError> {
+        self.witness = Some(add_signature(
+            self.witness.as_deref(),
+            secret_key,
+            &self.b_.serialize(),
+        )?);
+        Ok(())
+    }
+}
+
+fn hash_message(msg: &[u8]) -> Message {
+    Message::from_digest(sha256::Hash::hash(msg).to_byte_array())
+}
+
+pub(crate) fn parse_signatures(signatures: &[String]) -> Result<Vec<Signature>, MokshaCoreError> {
+    signatures
+        .iter()
+        .map(|sig| Signature::from_str(sig).map_err(|_| MokshaCoreError::InvalidWitness))
+        .collect()
+}
+
+pub(crate) fn count_valid_signatures(
+    msg: &[u8],
+    signatures: &[Signature],
+    keys: &[PublicKey],
+) -> u64 {
+    let secp = Secp256k1::verification_only();
+    let msg = hash_message(msg);
+    let mut keys = keys.to_vec();
+    keys.sort();
+    keys.dedup();
+    keys.iter()
+        .filter(|key| {
+            let (xonly, _) = key.x_only_public_key();
+            signatures
+                .iter()
+                .any(|sig| secp.verify_schnorr(sig, &msg, &xonly).is_ok())
+        })
+        .count() as u64
+}
+
+pub(crate) fn tag_with_keys(key: &str, pubkeys: &[PublicKey]) -> Vec<String> {
+    std::iter::once(key.to_owned())
+        .chain(pubkeys.iter().map(ToString::to_string))
+        .collect()
+}
+
+pub(crate) fn parse_pubkey(key: &str) -> Result<PublicKey, MokshaCoreError> {
+    PublicKey::from_str(key)
+        .map_err(|_| MokshaCoreError::InvalidSecret(format!("invalid pubkey {key}")))
+}
+
+pub(crate) fn parse_keys_tag(
+    secret: &WellKnownSecret,
+    key: &str,
+) -> Result<Vec<PublicKey>, MokshaCoreError> {
+    secret
+        .tag(key)
+        .unwrap_or_default()
+        .iter()
+        .map(|value| parse_pubkey(value))
+        .collect()
+}
+
+pub(crate) fn parse_number_tag(
+    secret: &WellKnownSecret,
+    key: &str,
+) -> Result<Option<u64>, MokshaCoreError> {
+    secret
+        .tag(key)
+        .and_then(|values| values.first())
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| MokshaCoreError::InvalidSecret(format!("invalid {key} {value}")))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+    use crate::{
+        p2pk::{P2PKConditions, SigFlag},
+        proof::Proof,
+        secret::WellKnownSecret,
+    };
+
+    fn keypair(byte: u8) -> (SecretKey, PublicKey) {
+        let secret_key = SecretKey::from_slice(&[byte; 32]).expect("invalid secret key");
+        (secret_key, secret_key.public_key(&Secp256k1::new()))
+    }
+
+    fn locked_proof(conditions: &P2PKConditions) -> Proof {
+        let (_, c) = keypair(9);
+        Proof::new(
+            8,
+            conditions.to_secret().to_string(),
+            c,
+            "00f4683f9caf8793".to_owned(),
+        )
+    }
+
+    fn verify(proof: &Proof, now: u64) -> Result<(), crate::error::MokshaCoreError> {
+        let secret =
+            WellKnownSecret::from_secret(&proof.secret)?.expect("secret is not well-known");
+        P2PKConditions::try_from(&secret)?.verify(
+            proof.secret.as_bytes(),
+            proof.witness.as_deref(),
+            now,
+        )
+    }
+
+    #[test]
+    fn test_conditions_roundtrip() -> anyhow::Result<()> {
+        let (_, pubkey) = keypair(1);
+        let (_, other) = keypair(2);
+        let (_, refund) = keypair(3);
+        let conditions = P2PKConditions::new(pubkey)
+            .with_additional_pubkeys(vec![other], 2)
+            .with_sig_flag(SigFlag::SigAll)
+            .with_locktime(1_700_000_000, vec![refund]);
+
+        let secret: WellKnownSecret = conditions.to_secret().to_string().parse()?;
+        assert_eq!(conditions, P2PKConditions::try_from(&secret)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_signature() -> anyhow::Result<()> {
+        let (secret_key, pubkey) = keypair(1);
+        let (wrong_key, _) = keypair(2);
+        let mut proof = locked_proof(&P2PKConditions::new(pubkey));
+        assert!(verify(&proof, 0).is_err());
+
+        let mut wrong = proof.clone();
+        wrong.sign_p2pk(&wrong_key)?;
+        assert!(verify(&wrong, 0).is_err());
+
+        proof.sign_p2pk(&secret_key)?;
+        assert!(verify(&proof, 0).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_n_sigs() -> anyhow::Result<()> {
+        let (key1, pubkey1) = keypair(1);
+        let (key2, pubkey2) = keypair(2);
+        let mut proof =
+            locked_proof(&P2PKConditions::new(pubkey1).with_additional_pubkeys(vec![pubkey2], 2));
+
+        proof.sign_p2pk(&key1)?;
+        proof.sign_p2pk(&key1)?;
+        assert!(verify(&proof, 0).is_err());
+
+        proof.sign_p2pk(&key2)?;
+        assert!(verify(&proof, 0).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_locktime() -> anyhow::Result<()> {
+        let (_, pubkey) = keypair(1);
+        let (refund_key, refund_pubkey) = keypair(3);
+
+        let no_refund = locked_proof(&P2PKConditions::new(pubkey).with_locktime(100, vec![]));
+        assert!(verify(&no_refund, 99).is_err());
+        assert!(verify(&no_refund, 100).is_ok());
+
+        let mut refund =
+            locked_proof(&P2PKConditions::new(pubkey).with_locktime(100, vec![refund_pubkey]));
+        assert!(verify(&refund, 100).is_err());
+        refund.sign_p2pk(&refund_key)?;
+        assert!(verify(&refund, 99).is_err());
+        assert!(verify(&refund, 100).is_ok());
+        Ok(())
+    }
+}
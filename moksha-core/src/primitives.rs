@@ -12,6 +12,7 @@ use uuid::Uuid;
 use crate::{
     blind::{BlindedMessage, BlindedSignature},
     proof::Proofs,
+    subscription::SubscriptionKind,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -42,6 +43,52 @@ pub struct PostSwapResponse {
     pub signatures: Vec<BlindedSignature>,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct PostCheckStateRequest {
+    /// Y = hash_to_curve(secret) of the proofs to check
+    #[serde(rename = "Ys")]
+    #[schema(value_type = Vec<String>)]
+    pub ys: Vec<PublicKey>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default, ToSchema)]
+pub struct PostCheckStateResponse {
+    pub states: Vec<ProofState>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct ProofState {
+    #[serde(rename = "Y")]
+    #[schema(value_type = String)]
+    pub y: PublicKey,
+    pub state: SpendState,
+    pub witness: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone, ToSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SpendState {
+    /// the proof has not been spent yet
+    Unspent,
+
+    /// the proof is used in a transaction that is not finished yet
+    Pending,
+
+    /// the proof has been spent
+    Spent,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct PostRestoreRequest {
+    pub outputs: Vec<BlindedMessage>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default, ToSchema)]
+pub struct PostRestoreResponse {
+    pub outputs: Vec<BlindedMessage>,
+    pub signatures: Vec<BlindedSignature>,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct CashuErrorResponse {
     pub code: u64,
@@ -127,6 +174,10 @@ pub struct PostMintQuoteBolt11Response {
     pub payment_request: String,
     pub paid: bool,
     pub expiry: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<CurrencyUnit>,
 }
 
 impl From<Bolt11MintQuote> for PostMintQuoteBolt11Response {
@@ -136,6 +187,8 @@ impl From<Bolt11MintQuote> for PostMintQuoteBolt11Response {
             payment_request: quote.payment_request,
             paid: quote.paid,
             expiry: Some(quote.expiry),
+            amount: Some(quote.amount),
+            unit: Some(quote.unit),
         }
     }
 }
@@ -164,13 +217,20 @@ pub struct PostMeltQuoteBolt11Response {
     pub amount: u64,
     pub fee_reserve: u64,
     pub paid: bool,
+    #[serde(default)]
+    pub state: MeltQuoteState,
     pub expiry: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_preimage: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Bolt11MintQuote {
     pub quote_id: Uuid,
     pub payment_request: String,
+    /// amount in the unit of the quote
+    pub amount: u64,
+    pub unit: CurrencyUnit,
     pub expiry: u64,
     pub paid: bool,
 }
@@ -178,11 +238,16 @@ pub struct Bolt11MintQuote {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Bolt11MeltQuote {
     pub quote_id: Uuid,
+    /// amount and fee reserve in the unit of the quote
     pub amount: u64,
     pub fee_reserve: u64,
+    pub unit: CurrencyUnit,
     pub payment_request: String,
     pub expiry: u64,
-    pub paid: bool,
+    pub state: MeltQuoteState,
+    pub payment_preimage: Option<String>,
+    /// lightning fees that were actually paid in the unit of the quote
+    pub fee_paid: Option<u64>,
 }
 
 impl From<Bolt11MeltQuote> for PostMeltQuoteBolt11Response {
@@ -192,7 +257,45 @@ impl From<Bolt11MeltQuote> for PostMeltQuoteBolt11Response {
             amount: quote.amount,
             fee_reserve: quote.fee_reserve,
             expiry: Some(quote.expiry),
-            paid: quote.paid,
+            paid: quote.state == MeltQuoteState::Paid,
+            state: quote.state,
+            payment_preimage: quote.payment_preimage,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, PartialEq, Eq, Clone, ToSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum MeltQuoteState {
+    /// initial state. The invoice has not been paid by the mint yet
+    #[default]
+    Unpaid,
+
+    /// the inputs are reserved and the payment is in flight
+    Pending,
+
+    Paid,
+}
+
+impl Display for MeltQuoteState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeltQuoteState::Unpaid => write!(f, "UNPAID"),
+            MeltQuoteState::Pending => write!(f, "PENDING"),
+            MeltQuoteState::Paid => write!(f, "PAID"),
+        }
+    }
+}
+
+impl FromStr for MeltQuoteState {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "UNPAID" => Ok(MeltQuoteState::Unpaid),
+            "PENDING" => Ok(MeltQuoteState::Pending),
+            "PAID" => Ok(MeltQuoteState::Paid),
+            _ => Err(()),
         }
     }
 }
@@ -207,6 +310,8 @@ pub struct PostMeltBolt11Request {
 #[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
 pub struct PostMeltBolt11Response {
     pub paid: bool,
+    #[serde(default)]
+    pub state: MeltQuoteState,
     pub payment_preimage: Option<String>,
     pub change: Vec<BlindedSignature>,
 }
@@ -497,14 +602,14 @@ impl Default for Nuts {
         Self {
             nut4: Nut4::default(),
             nut5: Nut5::default(),
-            nut7: Some(Nut7 { supported: false }),
+            nut7: Some(Nut7 { supported: true }),
             nut8: Some(Nut8 { supported: true }),
-            nut9: Some(Nut9 { supported: false }),
-            nut10: Some(Nut10 { supported: false }),
-            nut11: Some(Nut11 { supported: false }),
-            nut12: Some(Nut12 { supported: false }),
+            nut9: Some(Nut9 { supported: true }),
+            nut10: Some(Nut10 { supported: true }),
+            nut11: Some(Nut11 { supported: true }),
+            nut12: Some(Nut12 { supported: true }),
             nut13: Some(Nut13::default()),
-            nut14: Some(Nut14::default()),
+            nut14: Some(Nut14 { supported: true }),
             nut15: Some(Nut15::default()),
             nut16: Some(Nut16::default()),
             nut17: Some(Nut17::default()),
@@ -613,7 +718,34 @@ pub struct Nut16 {
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default, ToSchema)]
-pub struct Nut17 {}
+pub struct Nut17 {
+    /// the payment methods and units for which websocket subscriptions are supported
+    pub supported: Vec<Nut17Supported>,
+}
+
+impl Nut17 {
+    /// Returns true if the mint supports subscriptions of the given kind for the payment method and unit
+    pub fn supports(
+        &self,
+        kind: SubscriptionKind,
+        payment_method: &PaymentMethod,
+        unit: &CurrencyUnit,
+    ) -> bool {
+        self.supported.iter().any(|supported| {
+            supported.payment_method == *payment_method
+                && supported.unit == *unit
+                && supported.commands.contains(&kind)
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct Nut17Supported {
+    #[serde(rename = "method")]
+    pub payment_method: PaymentMethod,
+    pub unit: CurrencyUnit,
+    pub commands: Vec<SubscriptionKind>,
+}
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, ToSchema)]
 pub struct Nut18 {
@@ -693,7 +825,11 @@ mod tests {
     use crate::{
         dhke::public_key_from_hex,
         fixture::read_fixture,
-        primitives::{ContactInfoResponse, KeyResponse, MintInfoResponse, Nuts, PostSwapResponse},
+        primitives::{
+            ContactInfoResponse, CurrencyUnit, KeyResponse, MeltQuoteState, MintInfoResponse, Nuts,
+            PaymentMethod, PostMeltQuoteBolt11Response, PostSwapResponse,
+        },
+        subscription::SubscriptionKind,
     };
 
     #[test]
@@ -704,6 +840,22 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_deserialize_melt_quote_response_state() -> anyhow::Result<()> {
+        let response = serde_json::from_str::<PostMeltQuoteBolt11Response>(
+            r#"{"quote":"q","amount":10,"fee_reserve":2,"paid":false,"state":"PENDING","expiry":null}"#,
+        )?;
+        assert_eq!(MeltQuoteState::Pending, response.state);
+        assert_eq!(None, response.payment_preimage);
+
+        // older mints only send the paid flag
+        let response = serde_json::from_str::<PostMeltQuoteBolt11Response>(
+            r#"{"quote":"q","amount":10,"fee_reserve":2,"paid":false,"expiry":null}"#,
+        )?;
+        assert_eq!(MeltQuoteState::Unpaid, response.state);
+        Ok(())
+    }
+
     #[test]
     fn test_serialize_keyresponse() -> anyhow::Result<()> {
         let response = KeyResponse {
@@ -757,6 +909,17 @@ mod tests {
         assert!(info.is_ok());
         let info = info?;
         assert_eq!("Nutshell/0.16.0", info.version.unwrap());
+        let nut17 = info.nuts.nut17.expect("nut17 is None");
+        assert!(nut17.supports(
+            SubscriptionKind::Bolt11MintQuote,
+            &PaymentMethod::Bolt11,
+            &CurrencyUnit::Usd
+        ));
+        assert!(!nut17.supports(
+            SubscriptionKind::Bolt11MintQuote,
+            &PaymentMethod::BtcOnchain,
+            &CurrencyUnit::Sat
+        ));
         Ok(())
     }
 
@@ -4,18 +4,27 @@
 //!
 //! The `Proof` struct provides a `new` method for creating a new proof from its constituent fields.
 //!
+//! The optional `dleq` field contains the DLEQ proof of the mint together with the blinding factor `r`, so that the receiver of a token can verify the signature offline as described in [Nut-12](https://github.com/cashubtc/nuts/blob/main/12.md)
+//!
+//! The optional `witness` field contains the data required to unlock a proof with a spending condition, e.g. the signatures for a P2PK locked proof as described in [Nut-11](https://github.com/cashubtc/nuts/blob/main/11.md)
+//! or the preimage for a HTLC locked proof as described in [Nut-14](https://github.com/cashubtc/nuts/blob/main/14.md). The conditions can be parsed from the secret with `Proof::spending_conditions`.
+//!
 //! The `P2SHScript` struct represents a P2SH script, and is currently not implemented.
 //!
 //! The `Proofs` struct represents a collection of proofs, with a `Vec<Proof>` field for the proofs.
 //!
 //! Both the `Proof` and `Proofs` structs are serializable and deserializable using serde.
 
-use secp256k1::PublicKey;
+use secp256k1::{PublicKey, SecretKey};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use utoipa::ToSchema;
 
-use crate::{error::MokshaCoreError, keyset::KeysetId};
+use crate::{
+    error::MokshaCoreError,
+    keyset::KeysetId,
+    secret::{SpendingConditions, WellKnownSecret},
+};
 
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
@@ -28,6 +37,8 @@ pub struct Proof {
     #[schema(value_type = String)]
     pub c: PublicKey,
     pub script: Option<P2SHScript>,
+    pub dleq: Option<ProofDleq>,
+    pub witness: Option<String>,
 }
 
 impl Proof {
@@ -38,8 +49,32 @@ impl Proof {
             c,
             keyset_id: id,
             script: None,
+            dleq: None,
+            witness: None,
         }
     }
+
+    pub fn with_dleq(mut self, dleq: ProofDleq) -> Self {
+        self.dleq = Some(dleq);
+        self
+    }
+
+    /// Returns the spending conditions if the secret is a well-known secret (NUT-10) or None for a plain secret
+    pub fn spending_conditions(&self) -> Result<Option<SpendingConditions>, MokshaCoreError> {
+        WellKnownSecret::from_secret(&self.secret)?
+            .map(|secret| SpendingConditions::try_from(&secret))
+            .transpose()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct ProofDleq {
+    #[schema(value_type = String)]
+    pub e: SecretKey,
+    #[schema(value_type = String)]
+    pub s: SecretKey,
+    #[schema(value_type = String)]
+    pub r: SecretKey,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
@@ -109,6 +144,33 @@ impl Proofs {
 
         Ok(selected_proofs.into())
     }
+
+    /// Returns the fee for spending these proofs as inputs, which is the sum of the fees of the
+    /// keysets in parts per thousand rounded up to the next whole unit (NUT-02)
+    pub fn input_fee(&self, input_fee_ppk: impl Fn(&Proof) -> u64) -> u64 {
+        self.0
+            .iter()
+            .map(input_fee_ppk)
+            .sum::<u64>()
+            .div_ceil(1_000)
+    }
+
+    /// Selects proofs like `proofs_for_amount`, but the selected proofs also cover their own input fee
+    pub fn proofs_for_amount_with_fee(
+        &self,
+        amount: u64,
+        input_fee_ppk: impl Fn(&Proof) -> u64,
+    ) -> Result<Self, MokshaCoreError> {
+        let mut target = amount;
+        loop {
+            let selected = self.proofs_for_amount(target)?;
+            let required = amount + selected.input_fee(&input_fee_ppk);
+            if selected.total_amount() >= required {
+                return Ok(selected);
+            }
+            target = required;
+        }
+    }
 }
 
 impl From<Vec<Proof>> for Proofs {
@@ -160,6 +222,23 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_proofs_for_amount_with_fee() -> anyhow::Result<()> {
+        let fixture = read_fixture("token_60.cashu")?; // 60 tokens (4,8,16,32)
+        let proofs = TryInto::<TokenV3>::try_into(fixture)?.proofs();
+
+        assert_eq!(1, proofs.input_fee(|_| 100));
+        assert_eq!(0, proofs.input_fee(|_| 0));
+
+        // 32 does not cover the fee of 1 for the selected proof
+        let result = proofs.proofs_for_amount_with_fee(32, |_| 1_000)?;
+        assert_eq!(48, result.total_amount());
+        assert_eq!(2, result.len());
+
+        assert!(proofs.proofs_for_amount_with_fee(58, |_| 1_000).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_proof() -> anyhow::Result<()> {
         let js = json!(
@@ -0,0 +1,210 @@
+//! This module defines the well-known secret format for spending conditions as described in [Nut-10](https://github.com/cashubtc/nuts/blob/main/10.md)
+//!
+//! A well-known secret is serialized as a JSON array `[kind, {nonce, data, tags}]` and stored in the `secret` field of a `Proof`.
+//! Secrets that are not a JSON array are plain random secrets without any spending condition.
+//!
+//! The `WellKnownSecret` struct represents such a secret, with a `kind` field for the kind of the spending condition, a `nonce` field
+//! for a random nonce, a `data` field for the kind specific data and a `tags` field for additional conditions.
+//!
+//! The `SpendingConditions` enum contains the parsed conditions of all supported kinds.
+
+use std::{fmt::Display, str::FromStr};
+
+use secp256k1::rand::{thread_rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::MokshaCoreError, htlc::HTLCConditions, p2pk::P2PKConditions};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecretKind {
+    /// Pay-to-Public-Key (NUT-11)
+    P2PK,
+    /// Hashed Timelock Contract (NUT-14)
+    HTLC,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    from = "(SecretKind, WellKnownSecretData)",
+    into = "(SecretKind, WellKnownSecretData)"
+)]
+pub struct WellKnownSecret {
+    pub kind: SecretKind,
+    pub nonce: String,
+    pub data: String,
+    pub tags: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct WellKnownSecretData {
+    nonce: String,
+    data: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<Vec<String>>,
+}
+
+impl From<(SecretKind, WellKnownSecretData)> for WellKnownSecret {
+    fn from((kind, secret_data): (SecretKind, WellKnownSecretData)) -> Self {
+        Self {
+            kind,
+            nonce: secret_data.nonce,
+            data: secret_data.data,
+            tags: secret_data.tags,
+        }
+    }
+}
+
+impl From<WellKnownSecret> for (SecretKind, WellKnownSecretData) {
+    fn from(secret: WellKnownSecret) -> Self {
+        (
+            secret.kind,
+            WellKnownSecretData {
+                nonce: secret.nonce,
+                data: secret.data,
+                tags: secret.tags,
+            },
+        )
+    }
+}
+
+impl WellKnownSecret {
+    /// Creates a new secret with a random nonce
+    pub fn new(kind: SecretKind, data: impl Into<String>, tags: Vec<Vec<String>>) -> Self {
+        let mut nonce = [0u8; 32];
+        thread_rng().fill_bytes(&mut nonce);
+        Self {
+            kind,
+            nonce: hex::encode(nonce),
+            data: data.into(),
+            tags,
+        }
+    }
+
+    /// Parses the secret of a proof. Returns None if the secret is a plain secret without spending conditions.
+    pub fn from_secret(secret: &str) -> Result<Option<Self>, MokshaCoreError> {
+        if !secret.trim_start().starts_with('[') {
+            return Ok(None);
+        }
+        Ok(Some(secret.parse()?))
+    }
+
+    /// Returns all values of the first tag with the given key
+    pub fn tag(&self, key: &str) -> Option<&[String]> {
+        self.tags
+            .iter()
+            .find(|tag| tag.first().is_some_and(|k| k == key))
+            .map(|tag| &tag[1..])
+    }
+
+    /// Returns true if both secrets have the same conditions. The nonce is ignored.
+    pub fn has_same_conditions(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.data == other.data && self.tags == other.tags
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpendingConditions {
+    P2PK(P2PKConditions),
+    HTLC(HTLCConditions),
+}
+
+impl SpendingConditions {
+    /// Creates a new well-known secret with a random nonce for these conditions
+    pub fn to_secret(&self) -> WellKnownSecret {
+        match self {
+            Self::P2PK(conditions) => conditions.to_secret(),
+            Self::HTLC(conditions) => conditions.to_secret(),
+        }
+    }
+
+    /// Verifies the witness of a proof or blinded message against these conditions
+    pub fn verify(
+        &self,
+        msg: &[u8],
+        witness: Option<&str>,
+        now: u64,
+    ) -> Result<(), MokshaCoreError> {
+        match self {
+            Self::P2PK(conditions) => conditions.verify(msg, witness, now),
+            Self::HTLC(conditions) => conditions.verify(msg, witness, now),
+        }
+    }
+}
+
+impl TryFrom<&WellKnownSecret> for SpendingConditions {
+    type Error = MokshaCoreError;
+
+    fn try_from(secret: &WellKnownSecret) -> Result<Self, Self::Error> {
+        Ok(match secret.kind {
+            SecretKind::P2PK => Self::P2PK(secret.try_into()?),
+            SecretKind::HTLC => Self::HTLC(secret.try_into()?),
+        })
+    }
+}
+
+impl From<P2PKConditions> for SpendingConditions {
+    fn from(conditions: P2PKConditions) -> Self {
+        Self::P2PK(conditions)
+    }
+}
+
+impl From<HTLCConditions> for SpendingConditions {
+    fn from(conditions: HTLCConditions) -> Self {
+        Self::HTLC(conditions)
+    }
+}
+
+impl FromStr for WellKnownSecret {
+    type Err = MokshaCoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map_err(|_| MokshaCoreError::InvalidSecret(s.to_owned()))
+    }
+}
+
+impl Display for WellKnownSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{json}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::secret::{SecretKind, WellKnownSecret};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_deserialize_secret() -> anyhow::Result<()> {
+        let secret = r#"["P2PK",{"nonce":"859d4935c4907062a6297cf4e663e2835d90d97ecdd510745d32f6816323a41f","data":"0249098aa8b9d2fbec49ff8598feb17b592b986e62319a4fa488a3dc36387157a7","tags":[["sigflag","SIG_INPUTS"]]}]"#;
+        let secret = WellKnownSecret::from_secret(secret)?.expect("secret is not well-known");
+
+        assert_eq!(SecretKind::P2PK, secret.kind);
+        assert_eq!(
+            "0249098aa8b9d2fbec49ff8598feb17b592b986e62319a4fa488a3dc36387157a7",
+            secret.data
+        );
+        assert_eq!(Some(&["SIG_INPUTS".to_owned()][..]), secret.tag("sigflag"));
+        assert_eq!(None, secret.tag("locktime"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_secret() -> anyhow::Result<()> {
+        let secret = WellKnownSecret::new(SecretKind::P2PK, "data", vec![]);
+        let serialized = secret.to_string();
+        assert!(serialized.starts_with(r#"["P2PK",{"nonce":""#));
+        assert_eq!(secret, serialized.parse()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_plain_secret() -> anyhow::Result<()> {
+        assert_eq!(
+            None,
+            WellKnownSecret::from_secret("407915bc212be61a77e3e6d2aeb4c727")?
+        );
+        assert!(WellKnownSecret::from_secret(r#"["UNKNOWN",{}]"#).is_err());
+        Ok(())
+    }
+}
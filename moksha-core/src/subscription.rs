@@ -0,0 +1,276 @@
+//! This module contains the JSON-RPC messages of the websocket subscriptions (NUT-17).
+//! The wallet subscribes to the state of mint quotes, melt quotes or proofs and the mint sends a notification with
+//! the current state right after subscribing and on every change of the state.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::primitives::{PostMeltQuoteBolt11Response, PostMintQuoteBolt11Response, ProofState};
+
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// error codes of the JSON-RPC 2.0 specification
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionKind {
+    /// the filters are mint quote ids
+    Bolt11MintQuote,
+    /// the filters are melt quote ids
+    Bolt11MeltQuote,
+    /// the filters are the Ys of the proofs as hex
+    ProofState,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct WsRequest {
+    pub jsonrpc: String,
+    #[serde(flatten)]
+    pub method: WsMethod,
+    pub id: u64,
+}
+
+impl WsRequest {
+    pub fn new(method: WsMethod, id: u64) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_owned(),
+            method,
+            id,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum WsMethod {
+    Subscribe(SubscribeParams),
+    Unsubscribe(UnsubscribeParams),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SubscribeParams {
+    pub kind: SubscriptionKind,
+    #[serde(rename = "subId")]
+    pub sub_id: String,
+    pub filters: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct UnsubscribeParams {
+    #[serde(rename = "subId")]
+    pub sub_id: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct WsResponse {
+    pub jsonrpc: String,
+    #[serde(flatten)]
+    pub result: WsResult,
+    /// is None if the request could not be parsed
+    pub id: Option<u64>,
+}
+
+impl WsResponse {
+    pub fn ok(sub_id: String, id: u64) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_owned(),
+            result: WsResult::Result(WsResponseResult {
+                status: "OK".to_owned(),
+                sub_id,
+            }),
+            id: Some(id),
+        }
+    }
+
+    pub fn error(code: i64, message: String, id: Option<u64>) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_owned(),
+            result: WsResult::Error(WsError { code, message }),
+            id,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WsResult {
+    Result(WsResponseResult),
+    Error(WsError),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct WsResponseResult {
+    pub status: String,
+    #[serde(rename = "subId")]
+    pub sub_id: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct WsError {
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WsNotification {
+    pub jsonrpc: String,
+    /// is always "subscribe"
+    pub method: String,
+    pub params: NotificationParams,
+}
+
+impl WsNotification {
+    pub fn new(sub_id: String, payload: NotificationPayload) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_owned(),
+            method: "subscribe".to_owned(),
+            params: NotificationParams { sub_id, payload },
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct NotificationParams {
+    #[serde(rename = "subId")]
+    pub sub_id: String,
+    pub payload: NotificationPayload,
+}
+
+/// The payload has the same format as the responses of the http endpoints. The variants are tried in order, a melt
+/// quote is matched before a mint quote because it has the required field fee_reserve.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum NotificationPayload {
+    ProofState(ProofState),
+    MeltQuote(PostMeltQuoteBolt11Response),
+    MintQuote(PostMintQuoteBolt11Response),
+}
+
+/// A message that the mint sends to the wallet
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum WsMessage {
+    Response(WsResponse),
+    Notification(WsNotification),
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        dhke::public_key_from_hex,
+        primitives::{MeltQuoteState, ProofState, SpendState},
+        subscription::{
+            NotificationPayload, SubscribeParams, SubscriptionKind, UnsubscribeParams, WsMessage,
+            WsMethod, WsNotification, WsRequest, WsResponse, WsResult,
+        },
+    };
+
+    #[test]
+    fn test_deserialize_subscribe_request() -> anyhow::Result<()> {
+        let request = serde_json::from_str::<WsRequest>(
+            r#"{"jsonrpc":"2.0","id":0,"method":"subscribe","params":{"kind":"bolt11_mint_quote","subId":"sub","filters":["quote"]}}"#,
+        )?;
+        assert_eq!(
+            WsRequest::new(
+                WsMethod::Subscribe(SubscribeParams {
+                    kind: SubscriptionKind::Bolt11MintQuote,
+                    sub_id: "sub".to_owned(),
+                    filters: vec!["quote".to_owned()],
+                }),
+                0
+            ),
+            request
+        );
+
+        let request = serde_json::from_str::<WsRequest>(
+            r#"{"jsonrpc":"2.0","id":1,"method":"unsubscribe","params":{"subId":"sub"}}"#,
+        )?;
+        assert_eq!(
+            WsMethod::Unsubscribe(UnsubscribeParams {
+                sub_id: "sub".to_owned()
+            }),
+            request.method
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_response() -> anyhow::Result<()> {
+        let response = serde_json::to_string(&WsResponse::ok("sub".to_owned(), 2))?;
+        assert_eq!(
+            r#"{"jsonrpc":"2.0","result":{"status":"OK","subId":"sub"},"id":2}"#,
+            response
+        );
+
+        let response = serde_json::to_string(&WsResponse::error(-32602, "err".to_owned(), None))?;
+        assert_eq!(
+            r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"err"},"id":null}"#,
+            response
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_messages() -> anyhow::Result<()> {
+        let message = serde_json::from_str::<WsMessage>(
+            r#"{"jsonrpc":"2.0","result":{"status":"OK","subId":"sub"},"id":2}"#,
+        )?;
+        assert!(matches!(
+            message,
+            WsMessage::Response(WsResponse {
+                result: WsResult::Result(_),
+                ..
+            })
+        ));
+
+        let message = serde_json::from_str::<WsMessage>(
+            r#"{"jsonrpc":"2.0","method":"subscribe","params":{"subId":"sub","payload":{"quote":"q","request":"lnbc1","paid":true,"expiry":10}}}"#,
+        )?;
+        let WsMessage::Notification(notification) = message else {
+            panic!("expected notification");
+        };
+        assert_eq!("sub", notification.params.sub_id);
+        assert!(
+            matches!(notification.params.payload, NotificationPayload::MintQuote(quote) if quote.paid)
+        );
+
+        let message = serde_json::from_str::<WsMessage>(
+            r#"{"jsonrpc":"2.0","method":"subscribe","params":{"subId":"sub","payload":{"quote":"q","amount":10,"fee_reserve":2,"paid":false,"state":"PENDING","expiry":10}}}"#,
+        )?;
+        let WsMessage::Notification(notification) = message else {
+            panic!("expected notification");
+        };
+        assert!(
+            matches!(notification.params.payload, NotificationPayload::MeltQuote(quote) if quote.state == MeltQuoteState::Pending)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_roundtrip_proof_state_notification() -> anyhow::Result<()> {
+        let y = public_key_from_hex(
+            "02a9acc1e48c25eeeb9289b5031cc57da9fe72f3fe2861d264bdc074209b107ba2",
+        );
+        let notification = WsNotification::new(
+            "sub".to_owned(),
+            NotificationPayload::ProofState(ProofState {
+                y,
+                state: SpendState::Spent,
+                witness: None,
+            }),
+        );
+        let message = serde_json::from_str::<WsMessage>(&serde_json::to_string(&notification)?)?;
+        let WsMessage::Notification(notification) = message else {
+            panic!("expected notification");
+        };
+        assert!(
+            matches!(notification.params.payload, NotificationPayload::ProofState(state) if state.y == y && state.state == SpendState::Spent)
+        );
+        Ok(())
+    }
+}
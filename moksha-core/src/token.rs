@@ -281,6 +281,8 @@ mod tests {
                 ),
                 keyset_id: "someid".to_string(),
                 script: None,
+                dleq: None,
+                witness: None,
             }
             .into(),
         };
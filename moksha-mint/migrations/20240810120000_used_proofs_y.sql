@@ -0,0 +1,4 @@
+-- Y = hash_to_curve(secret) of a spent proof. Used by the checkstate endpoint (NUT-07)
+ALTER TABLE used_proofs ADD COLUMN y TEXT;
+
+CREATE INDEX used_proofs_y_idx ON used_proofs (y);
@@ -0,0 +1,9 @@
+-- all blind signatures issued by the mint, needed to restore tokens from the seed (NUT-09)
+CREATE TABLE blind_signatures (
+    b_ TEXT NOT NULL PRIMARY KEY,
+    amount BIGINT NOT NULL,
+    c_ TEXT NOT NULL,
+    keyset_id TEXT NOT NULL,
+    dleq_e TEXT,
+    dleq_s TEXT
+);
@@ -0,0 +1,9 @@
+-- registry of all keysets of the mint, inactive keysets are kept to redeem old proofs
+CREATE TABLE keysets (
+    id TEXT NOT NULL PRIMARY KEY,
+    unit TEXT NOT NULL,
+    derivation_path TEXT NOT NULL,
+    active BOOLEAN NOT NULL,
+    input_fee_ppk BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
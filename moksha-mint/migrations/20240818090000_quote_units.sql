@@ -0,0 +1,8 @@
+-- amounts of bolt11 quotes are stored in the unit of the quote
+ALTER TABLE bolt11_mint_quotes ADD COLUMN amount BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE bolt11_mint_quotes ADD COLUMN unit TEXT NOT NULL DEFAULT 'sat';
+ALTER TABLE bolt11_melt_quotes ADD COLUMN unit TEXT NOT NULL DEFAULT 'sat';
+
+-- quotes that are not minted yet still have a pending invoice with the amount in sat
+UPDATE bolt11_mint_quotes SET amount = pending_invoices.amount
+FROM pending_invoices WHERE pending_invoices.key = bolt11_mint_quotes.id::text;
@@ -0,0 +1,3 @@
+-- a proof can only be spent once, the unique index makes inserting spent proofs the double-spend check
+DROP INDEX used_proofs_y_idx;
+CREATE UNIQUE INDEX used_proofs_y_idx ON used_proofs (y);
@@ -0,0 +1,10 @@
+-- proofs that are reserved while a melt is in flight, they are neither spendable nor spent until the payment settles
+CREATE TABLE pending_proofs (
+    y TEXT NOT NULL PRIMARY KEY,
+    amount BIGINT NOT NULL,
+    secret TEXT NOT NULL,
+    c TEXT NOT NULL,
+    keyset_id TEXT NOT NULL,
+    quote_id UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
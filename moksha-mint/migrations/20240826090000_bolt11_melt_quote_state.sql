@@ -0,0 +1,19 @@
+-- melt quotes are UNPAID, PENDING while the payment is in flight or PAID with the preimage and the fees actually paid
+ALTER TABLE bolt11_melt_quotes
+ADD COLUMN state TEXT;
+
+UPDATE bolt11_melt_quotes
+SET state = CASE
+    WHEN paid = true THEN 'PAID'
+    WHEN paid = false THEN 'UNPAID'
+END;
+
+ALTER TABLE bolt11_melt_quotes
+DROP COLUMN paid;
+
+ALTER TABLE bolt11_melt_quotes
+ALTER COLUMN state SET NOT NULL;
+
+ALTER TABLE bolt11_melt_quotes
+ADD COLUMN payment_preimage TEXT,
+ADD COLUMN fee_paid BIGINT;
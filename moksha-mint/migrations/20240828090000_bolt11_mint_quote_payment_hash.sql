@@ -0,0 +1,3 @@
+-- the payment hash of the invoice is used to detect melts that pay an invoice of this mint
+ALTER TABLE bolt11_mint_quotes ADD COLUMN payment_hash TEXT;
+CREATE INDEX bolt11_mint_quotes_payment_hash_idx ON bolt11_mint_quotes (payment_hash);
@@ -0,0 +1,12 @@
+-- ledger of the ecash issued and redeemed per keyset, amounts are in the unit of the keyset
+CREATE TABLE liabilities (
+    keyset_id TEXT NOT NULL PRIMARY KEY,
+    unit TEXT NOT NULL,
+    minted BIGINT NOT NULL DEFAULT 0,
+    melted BIGINT NOT NULL DEFAULT 0,
+    melt_change BIGINT NOT NULL DEFAULT 0,
+    swapped_in BIGINT NOT NULL DEFAULT 0,
+    swapped_out BIGINT NOT NULL DEFAULT 0,
+    fees BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
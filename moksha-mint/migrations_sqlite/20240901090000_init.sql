@@ -0,0 +1,87 @@
+-- the sqlite schema starts with the current state of the postgres migrations, uuids are stored as blobs
+CREATE TABLE used_proofs (
+    amount INTEGER NOT NULL,
+    secret TEXT NOT NULL PRIMARY KEY,
+    c TEXT NOT NULL,
+    keyset_id TEXT NOT NULL,
+    y TEXT NOT NULL
+);
+
+CREATE UNIQUE INDEX used_proofs_y_idx ON used_proofs (y);
+
+CREATE TABLE pending_proofs (
+    y TEXT NOT NULL PRIMARY KEY,
+    amount INTEGER NOT NULL,
+    secret TEXT NOT NULL,
+    c TEXT NOT NULL,
+    keyset_id TEXT NOT NULL,
+    quote_id BLOB,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE pending_invoices (
+    key TEXT NOT NULL PRIMARY KEY,
+    payment_request TEXT NOT NULL,
+    amount INTEGER NOT NULL
+);
+
+CREATE TABLE blind_signatures (
+    b_ TEXT NOT NULL PRIMARY KEY,
+    amount INTEGER NOT NULL,
+    c_ TEXT NOT NULL,
+    keyset_id TEXT NOT NULL,
+    dleq_e TEXT,
+    dleq_s TEXT
+);
+
+CREATE TABLE keysets (
+    id TEXT NOT NULL PRIMARY KEY,
+    unit TEXT NOT NULL,
+    derivation_path TEXT NOT NULL,
+    active BOOLEAN NOT NULL,
+    input_fee_ppk INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE bolt11_mint_quotes (
+    id BLOB NOT NULL PRIMARY KEY,
+    payment_request TEXT NOT NULL,
+    payment_hash TEXT,
+    amount INTEGER NOT NULL,
+    unit TEXT NOT NULL,
+    expiry INTEGER NOT NULL,
+    paid BOOLEAN NOT NULL
+);
+
+CREATE INDEX bolt11_mint_quotes_payment_hash_idx ON bolt11_mint_quotes (payment_hash);
+
+CREATE TABLE bolt11_melt_quotes (
+    id BLOB NOT NULL PRIMARY KEY,
+    payment_request TEXT NOT NULL,
+    expiry INTEGER NOT NULL,
+    state TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    fee_reserve INTEGER NOT NULL,
+    unit TEXT NOT NULL,
+    payment_preimage TEXT,
+    fee_paid INTEGER
+);
+
+CREATE TABLE onchain_mint_quotes (
+    id BLOB NOT NULL PRIMARY KEY,
+    address TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    expiry INTEGER NOT NULL,
+    state TEXT NOT NULL
+);
+
+CREATE TABLE onchain_melt_quotes (
+    id BLOB NOT NULL PRIMARY KEY,
+    amount INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    fee_total INTEGER NOT NULL,
+    fee_sat_per_vbyte INTEGER NOT NULL,
+    expiry INTEGER NOT NULL,
+    state TEXT NOT NULL,
+    description TEXT
+);
@@ -0,0 +1,12 @@
+-- ledger of the ecash issued and redeemed per keyset, amounts are in the unit of the keyset
+CREATE TABLE liabilities (
+    keyset_id TEXT NOT NULL PRIMARY KEY,
+    unit TEXT NOT NULL,
+    minted INTEGER NOT NULL DEFAULT 0,
+    melted INTEGER NOT NULL DEFAULT 0,
+    melt_change INTEGER NOT NULL DEFAULT 0,
+    swapped_in INTEGER NOT NULL DEFAULT 0,
+    swapped_out INTEGER NOT NULL DEFAULT 0,
+    fees INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
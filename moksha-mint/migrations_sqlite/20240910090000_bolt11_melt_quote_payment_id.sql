@@ -0,0 +1,2 @@
+-- the id under which the lightning backend looks up the payment of the quote, if it isn't the payment hash
+ALTER TABLE bolt11_melt_quotes ADD COLUMN payment_id TEXT;
@@ -1,6 +1,9 @@
 use mokshamint::{
-    config::{MintConfig, TracingConfig},
-    mint::MintBuilder,
+    config::{Command, DatabaseType, MintConfig, TracingConfig},
+    database::{postgres::PostgresDB, sqlite::SqliteDB, Database},
+    mint::{Mint, MintBuilder},
+    model::LiabilitiesReport,
+    server::run_server,
 };
 use std::env;
 use tracing_subscriber::{filter::EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
@@ -25,34 +28,114 @@ pub async fn main() -> anyhow::Result<()> {
         };
     }
 
-    let MintConfig {
-        privatekey,
-        derivation_path,
-        info,
-        lightning_fee,
-        server,
-        btconchain_backend,
-        lightning_backend,
-        tracing,
-        database,
-    } = MintConfig::read_config_with_defaults();
+    let (
+        MintConfig {
+            privatekey,
+            derivation_path,
+            input_fee_ppk,
+            info,
+            lightning_fee,
+            server,
+            btconchain_backend,
+            lightning_backend,
+            tracing,
+            database,
+            price_oracle,
+        },
+        command,
+    ) = MintConfig::read_config_and_command();
 
     init_tracing(tracing.clone())?;
 
-    let mint = MintBuilder::new()
+    let builder = MintBuilder::new()
         .with_mint_info(Some(info))
         .with_server(Some(server))
         .with_private_key(privatekey)
         .with_derivation_path(derivation_path)
-        .with_db(Some(database))
-        .with_lightning(lightning_backend.expect("lightning not set"))
+        .with_input_fee_ppk(input_fee_ppk)
+        .with_db(Some(database.clone()))
         .with_btc_onchain(btconchain_backend)
         .with_fee(Some(lightning_fee))
         .with_tracing(tracing)
-        .build()
-        .await;
+        .with_price_oracle(price_oracle);
+    let builder = match lightning_backend {
+        Some(lightning_backend) => builder.with_lightning(lightning_backend),
+        None => builder,
+    };
+
+    match database.db_type() {
+        Some(DatabaseType::Postgres) => {
+            let db = PostgresDB::new(&database).await?;
+            db.migrate().await;
+            run(builder.with_database(db).build().await?, command).await
+        }
+        Some(DatabaseType::Sqlite) => {
+            let db = SqliteDB::new(&database).await?;
+            db.migrate().await;
+            run(builder.with_database(db).build().await?, command).await
+        }
+        None => anyhow::bail!("MINT_DB_URL must start with postgres:// or sqlite:"),
+    }
+}
+
+async fn run<DB: Database>(mint: Mint<DB>, command: Option<Command>) -> anyhow::Result<()> {
+    match command {
+        None => run_server(mint).await,
+        Some(Command::Liabilities) => {
+            print_liabilities(&mint.liabilities_report().await?);
+            Ok(())
+        }
+    }
+}
 
-    mokshamint::server::run_server(mint?).await
+fn print_liabilities(report: &LiabilitiesReport) {
+    println!(
+        "{:<18} {:>5} {:>12} {:>12} {:>12} {:>12} {:>12} {:>10} {:>12}",
+        "keyset",
+        "unit",
+        "minted",
+        "melted",
+        "melt change",
+        "swapped in",
+        "swapped out",
+        "fees",
+        "outstanding"
+    );
+    for keyset in &report.keysets {
+        println!(
+            "{:<18} {:>5} {:>12} {:>12} {:>12} {:>12} {:>12} {:>10} {:>12}",
+            keyset.keyset_id,
+            keyset.unit,
+            keyset.minted,
+            keyset.melted,
+            keyset.melt_change,
+            keyset.swapped_in,
+            keyset.swapped_out,
+            keyset.fees,
+            keyset.outstanding()
+        );
+    }
+    println!();
+    for unit in &report.units {
+        println!(
+            "outstanding {}: {} (fees: {})",
+            unit.unit, unit.outstanding, unit.fees
+        );
+    }
+    let format_balance = |balance: Option<u64>| {
+        balance.map_or_else(|| "not reported".to_owned(), |balance| balance.to_string())
+    };
+    println!(
+        "lightning balance msat: {}",
+        format_balance(report.lightning_balance_msat)
+    );
+    println!(
+        "onchain balance sat: {}",
+        format_balance(report.onchain_balance_sat)
+    );
+    if let Some(surplus_msat) = report.surplus_msat {
+        println!("surplus msat: {surplus_msat}");
+    }
 }
 
 fn init_tracing(tr: Option<TracingConfig>) -> anyhow::Result<()> {
@@ -1,9 +1,11 @@
 #![allow(clippy::blocks_in_conditions)]
 use super::{BtcOnchain, EstimateFeeResult, SendCoinsResult};
-use crate::error::MokshaMintError;
+use crate::{error::MokshaMintError, lightning::error::LightningError};
 use async_trait::async_trait;
 use fedimint_tonic_lnd::{
-    lnrpc::{AddressType, EstimateFeeRequest, NewAddressRequest, SendCoinsRequest},
+    lnrpc::{
+        AddressType, EstimateFeeRequest, NewAddressRequest, SendCoinsRequest, WalletBalanceRequest,
+    },
     walletrpc::ListUnspentRequest,
     Client,
 };
@@ -119,7 +121,15 @@ impl BtcOnchain for LndBtcOnchain {
                 sat_per_vbyte: sat_per_vbyte as u64,
                 ..Default::default()
             })
-            .await?
+            .await
+            .map_err(|status| {
+                // lnd can't fund the transaction, so nothing was broadcast
+                if status.message().contains("insufficient funds") {
+                    MokshaMintError::Lightning(LightningError::PaymentFailed)
+                } else {
+                    status.into()
+                }
+            })?
             .into_inner();
 
         Ok(SendCoinsResult {
@@ -127,6 +137,17 @@ impl BtcOnchain for LndBtcOnchain {
         })
     }
 
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_balance(&self) -> Result<Option<u64>, MokshaMintError> {
+        let response = self
+            .client_lock()
+            .await?
+            .wallet_balance(WalletBalanceRequest {})
+            .await?
+            .into_inner();
+        Ok(Some(response.confirmed_balance as u64))
+    }
+
     #[instrument(level = "debug", skip(self), err)]
     async fn estimate_fee(
         &self,
@@ -11,6 +11,8 @@ use mockall::automock;
 #[async_trait]
 pub trait BtcOnchain: Send + Sync {
     async fn new_address(&self) -> Result<String, MokshaMintError>;
+    /// Sends the coins. Fails with `LightningError::PaymentFailed` only if the transaction was definitely not
+    /// broadcast, the proofs of a melt that fails with any other error stay pending.
     async fn send_coins(
         &self,
         address: &str,
@@ -32,6 +34,12 @@ pub trait BtcOnchain: Send + Sync {
     ) -> Result<bool, MokshaMintError>;
 
     async fn is_transaction_paid(&self, txid: &str) -> Result<bool, MokshaMintError>;
+
+    /// Returns the confirmed balance of the onchain wallet in sat. Returns None if the backend can't report its
+    /// balance.
+    async fn get_balance(&self) -> Result<Option<u64>, MokshaMintError> {
+        Ok(None)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1,27 +1,133 @@
+use std::str::FromStr;
+
 use async_trait::async_trait;
+use lightning_invoice::Bolt11Invoice;
 use moksha_core::{
-    primitives::{Bolt11MeltQuote, Bolt11MintQuote, BtcOnchainMeltQuote, BtcOnchainMintQuote},
+    blind::{BlindedMessage, BlindedSignature},
+    primitives::{
+        Bolt11MeltQuote, Bolt11MintQuote, BtcOnchainMeltQuote, BtcOnchainMintQuote, MeltQuoteState,
+    },
     proof::Proofs,
 };
+use secp256k1::PublicKey;
 use uuid::Uuid;
 
-use crate::{error::MokshaMintError, model::Invoice};
+use crate::{
+    error::MokshaMintError,
+    model::{Invoice, KeysetInfo, Liabilities, MeltQuoteFilter, MintQuoteFilter},
+};
 
 pub mod postgres;
+pub mod sqlite;
+#[cfg(test)]
+pub(crate) mod test_db;
+
+/// number of quotes that are returned by a search without a limit
+const DEFAULT_SEARCH_LIMIT: u32 = 100;
+
+/// Splits the search term of a quote filter into a quote id and a term for the other columns, so a quote id is
+/// matched exactly
+fn search_term(search: Option<&str>) -> (Option<Uuid>, Option<String>) {
+    match search.map(Uuid::from_str) {
+        Some(Ok(id)) => (Some(id), None),
+        _ => (None, search.map(ToOwned::to_owned)),
+    }
+}
+
+/// the payment hash of a bolt11 invoice or None if the payment request can't be decoded
+fn payment_hash(payment_request: &str) -> Option<String> {
+    Bolt11Invoice::from_str(payment_request)
+        .ok()
+        .map(|invoice| invoice.payment_hash().to_string())
+}
 
 #[async_trait]
-pub trait Database {
+pub trait Database: Clone + Send + Sync + 'static {
     type DB: sqlx::Database;
     async fn begin_tx(&self) -> Result<sqlx::Transaction<Self::DB>, sqlx::Error>;
-    async fn get_used_proofs(
+    /// Marks the proofs as spent. Fails with `ProofAlreadyUsed` if one of the proofs is already spent or contained twice
+    /// and with `ProofPending` if it is reserved by a melt, the transaction must not be committed in this case.
+    /// Concurrent transactions with the same proofs block on the unique index of Y until the first one is committed or
+    /// rolled back.
+    async fn add_used_proofs(
         &self,
         tx: &mut sqlx::Transaction<Self::DB>,
-    ) -> Result<Proofs, MokshaMintError>;
-    async fn add_used_proofs(
+        proofs: &Proofs,
+    ) -> Result<(), MokshaMintError>;
+    /// returns the subset of the given Y values that belong to spent proofs
+    async fn get_spent_ys(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        ys: &[PublicKey],
+    ) -> Result<Vec<PublicKey>, MokshaMintError>;
+
+    /// Reserves the proofs for the melt with the given quote while the payment is in flight. Fails with `ProofPending`
+    /// if one of the proofs is already reserved and with `ProofAlreadyUsed` if it is spent.
+    async fn add_pending_proofs(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        proofs: &Proofs,
+        quote_id: Option<&Uuid>,
+    ) -> Result<(), MokshaMintError>;
+    /// releases the reservation of the proofs
+    async fn delete_pending_proofs(
         &self,
         tx: &mut sqlx::Transaction<Self::DB>,
         proofs: &Proofs,
     ) -> Result<(), MokshaMintError>;
+    /// returns all reserved proofs grouped by the quote of their melt
+    async fn get_pending_proofs(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+    ) -> Result<Vec<(Uuid, Proofs)>, MokshaMintError>;
+    /// returns the subset of the given Y values that belong to pending proofs
+    async fn get_pending_ys(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        ys: &[PublicKey],
+    ) -> Result<Vec<PublicKey>, MokshaMintError>;
+
+    async fn add_blind_signatures(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        outputs: &[BlindedMessage],
+        signatures: &[BlindedSignature],
+    ) -> Result<(), MokshaMintError>;
+    /// returns the stored outputs and their signatures for all known B_ values
+    async fn get_blind_signatures(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        b_s: &[PublicKey],
+    ) -> Result<Vec<(BlindedMessage, BlindedSignature)>, MokshaMintError>;
+
+    async fn get_keysets(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+    ) -> Result<Vec<KeysetInfo>, MokshaMintError>;
+    async fn add_keyset(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        keyset: &KeysetInfo,
+    ) -> Result<(), MokshaMintError>;
+    /// sets the active flag of the keyset with the given id
+    async fn update_keyset_active(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        id: &str,
+        active: bool,
+    ) -> Result<(), MokshaMintError>;
+
+    /// Adds the amounts to the ledger of their keysets, so the ledger is updated in the transaction of the operation
+    async fn add_liabilities(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        liabilities: &[Liabilities],
+    ) -> Result<(), MokshaMintError>;
+    /// returns the ledger of every keyset that issued or redeemed ecash
+    async fn get_liabilities(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+    ) -> Result<Vec<Liabilities>, MokshaMintError>;
 
     async fn get_pending_invoice(
         &self,
@@ -45,6 +151,42 @@ pub trait Database {
         tx: &mut sqlx::Transaction<Self::DB>,
         key: &Uuid,
     ) -> Result<Bolt11MintQuote, MokshaMintError>;
+    /// Returns the unpaid mint quote whose invoice has the given payment hash. The quote is locked until the
+    /// transaction ends, so an invoice of this mint can only be settled internally by one melt
+    async fn get_unpaid_bolt11_mint_quote_by_payment_hash(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        payment_hash: &str,
+    ) -> Result<Option<Bolt11MintQuote>, MokshaMintError>;
+    /// Returns the mint quote whose invoice has the given payment hash, regardless of its paid state
+    async fn get_bolt11_mint_quote_by_payment_hash(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        payment_hash: &str,
+    ) -> Result<Option<Bolt11MintQuote>, MokshaMintError>;
+    async fn get_unpaid_bolt11_mint_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+    ) -> Result<Vec<Bolt11MintQuote>, MokshaMintError>;
+    /// Returns the mint quotes that match the filter, the quotes that expire last come first
+    async fn search_bolt11_mint_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        filter: &MintQuoteFilter,
+    ) -> Result<Vec<Bolt11MintQuote>, MokshaMintError>;
+    /// Sets the expiry of the mint quote if it is not paid. Returns false if the quote is paid or doesn't exist.
+    async fn update_bolt11_mint_quote_expiry(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+        expiry: u64,
+    ) -> Result<bool, MokshaMintError>;
+    /// Returns the unpaid mint quotes that expired before the given unix timestamp
+    async fn get_expired_bolt11_mint_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        expired_before: u64,
+    ) -> Result<Vec<Bolt11MintQuote>, MokshaMintError>;
     async fn add_bolt11_mint_quote(
         &self,
         tx: &mut sqlx::Transaction<Self::DB>,
@@ -55,6 +197,13 @@ pub trait Database {
         tx: &mut sqlx::Transaction<Self::DB>,
         quote: &Bolt11MintQuote,
     ) -> Result<(), MokshaMintError>;
+    /// Marks the mint quote as paid if it isn't paid yet. Returns false otherwise, so only one of several concurrent
+    /// internal settlements of the same invoice succeeds.
+    async fn mark_bolt11_mint_quote_paid(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+    ) -> Result<bool, MokshaMintError>;
     async fn delete_bolt11_mint_quote(
         &self,
         tx: &mut sqlx::Transaction<Self::DB>,
@@ -76,7 +225,52 @@ pub trait Database {
         tx: &mut sqlx::Transaction<Self::DB>,
         quote: &Bolt11MeltQuote,
     ) -> Result<(), MokshaMintError>;
+    /// Returns the melt quotes that match the filter, the quotes that expire last come first
+    async fn search_bolt11_melt_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        filter: &MeltQuoteFilter,
+    ) -> Result<Vec<Bolt11MeltQuote>, MokshaMintError>;
+    /// Sets the expiry of the melt quote if it is unpaid. Returns false if the quote is pending, paid or doesn't
+    /// exist.
+    async fn update_bolt11_melt_quote_expiry(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+        expiry: u64,
+    ) -> Result<bool, MokshaMintError>;
+    /// Sets the state of the melt quote if it is currently in the `from` state and returns false otherwise,
+    /// so only one of several concurrent melts of the same quote can move it to `PENDING`
+    async fn transition_bolt11_melt_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+        from: &MeltQuoteState,
+        to: &MeltQuoteState,
+    ) -> Result<bool, MokshaMintError>;
+
+    /// Stores the id under which the lightning backend looks up the payment of the melt quote, for backends that
+    /// don't identify payments by their payment hash
+    async fn set_bolt11_melt_quote_payment_id(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+        payment_id: &str,
+    ) -> Result<(), MokshaMintError>;
+    /// Returns the payment id stored for the melt quote, None if the payment is identified by its payment hash
+    async fn get_bolt11_melt_quote_payment_id(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+    ) -> Result<Option<String>, MokshaMintError>;
 
+    /// Deletes the unpaid melt quotes that expired before the given unix timestamp and have no pending proofs.
+    /// Returns the number of deleted quotes.
+    async fn delete_expired_bolt11_melt_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        expired_before: u64,
+    ) -> Result<u64, MokshaMintError>;
     async fn delete_bolt11_melt_quote(
         &self,
         tx: &mut sqlx::Transaction<Self::DB>,
@@ -89,6 +283,13 @@ pub trait Database {
         key: &Uuid,
     ) -> Result<BtcOnchainMintQuote, MokshaMintError>;
 
+    /// Returns the unpaid onchain mint quotes that expired before the given unix timestamp
+    async fn get_expired_onchain_mint_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        expired_before: u64,
+    ) -> Result<Vec<BtcOnchainMintQuote>, MokshaMintError>;
+
     async fn add_onchain_mint_quote(
         &self,
         tx: &mut sqlx::Transaction<Self::DB>,
@@ -125,6 +326,14 @@ pub trait Database {
         quote: &BtcOnchainMeltQuote,
     ) -> Result<(), MokshaMintError>;
 
+    /// Deletes the unpaid onchain melt quotes that expired before the given unix timestamp and have no pending
+    /// proofs. Returns the number of deleted quotes.
+    async fn delete_expired_onchain_melt_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        expired_before: u64,
+    ) -> Result<u64, MokshaMintError>;
+
     async fn delete_onchain_melt_quote(
         &self,
         tx: &mut sqlx::Transaction<Self::DB>,
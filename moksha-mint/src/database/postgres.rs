@@ -1,23 +1,29 @@
 #![allow(clippy::blocks_in_conditions)]
-use std::str::FromStr;
+use std::{collections::HashSet, str::FromStr};
 
 use async_trait::async_trait;
 use moksha_core::{
-    dhke,
+    blind::{BlindedMessage, BlindedSignature, DleqProof},
+    dhke::{self, Dhke},
     primitives::{
         Bolt11MeltQuote, Bolt11MintQuote, BtcOnchainMeltQuote, BtcOnchainMintQuote, CurrencyUnit,
-        MeltBtcOnchainState, MintBtcOnchainState,
+        MeltBtcOnchainState, MeltQuoteState, MintBtcOnchainState,
     },
     proof::{Proof, Proofs},
 };
 
+use secp256k1::{PublicKey, SecretKey};
 use sqlx::postgres::PgPoolOptions;
 use tracing::instrument;
 use uuid::Uuid;
 
-use crate::{config::DatabaseConfig, error::MokshaMintError, model::Invoice};
+use crate::{
+    config::DatabaseConfig,
+    error::MokshaMintError,
+    model::{Invoice, KeysetInfo, Liabilities, MeltQuoteFilter, MintQuoteFilter},
+};
 
-use super::Database;
+use super::{payment_hash, search_term, Database, DEFAULT_SEARCH_LIMIT};
 
 #[derive(Clone)]
 pub struct PostgresDB {
@@ -39,6 +45,123 @@ impl PostgresDB {
             .run(&self.pool)
             .await
             .expect("Could not run migrations");
+        self.fill_missing_proof_ys()
+            .await
+            .expect("Could not fill missing Y values of used proofs");
+        self.fill_missing_payment_hashes()
+            .await
+            .expect("Could not fill missing payment hashes of mint quotes");
+    }
+
+    /// used proofs that were stored before the y column existed don't have a Y value yet
+    async fn fill_missing_proof_ys(&self) -> Result<(), MokshaMintError> {
+        let mut tx = self.pool.begin().await?;
+        let secrets = sqlx::query!("SELECT secret FROM used_proofs WHERE y IS NULL")
+            .fetch_all(&mut *tx)
+            .await?;
+
+        for row in secrets {
+            let y = Dhke::hash_to_curve(row.secret.as_bytes())?.to_string();
+            sqlx::query!(
+                "UPDATE used_proofs SET y = $1 WHERE secret = $2",
+                y,
+                row.secret
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// mint quotes that were stored before the payment_hash column existed don't have a payment hash yet
+    async fn fill_missing_payment_hashes(&self) -> Result<(), MokshaMintError> {
+        let mut tx = self.pool.begin().await?;
+        let quotes = sqlx::query!(
+            "SELECT id, payment_request FROM bolt11_mint_quotes WHERE payment_hash IS NULL"
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for row in quotes {
+            let Some(hash) = payment_hash(&row.payment_request) else {
+                continue;
+            };
+            sqlx::query!(
+                "UPDATE bolt11_mint_quotes SET payment_hash = $1 WHERE id = $2",
+                hash,
+                row.id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// the columns of proofs as arrays, so a batch of proofs can be inserted with a single UNNEST query
+struct ProofColumns {
+    amounts: Vec<i64>,
+    secrets: Vec<String>,
+    cs: Vec<String>,
+    keyset_ids: Vec<String>,
+    ys: Vec<String>,
+}
+
+impl ProofColumns {
+    fn new(proofs: &Proofs) -> Result<Self, MokshaMintError> {
+        let proofs = proofs.proofs();
+        Ok(Self {
+            amounts: proofs.iter().map(|proof| proof.amount as i64).collect(),
+            secrets: proofs.iter().map(|proof| proof.secret.clone()).collect(),
+            cs: proofs.iter().map(|proof| proof.c.to_string()).collect(),
+            keyset_ids: proofs.iter().map(|proof| proof.keyset_id.clone()).collect(),
+            ys: proofs
+                .iter()
+                .map(|proof| Ok(Dhke::hash_to_curve(proof.secret.as_bytes())?.to_string()))
+                .collect::<Result<Vec<_>, MokshaMintError>>()?,
+        })
+    }
+}
+
+impl PostgresDB {
+    /// Inserts the proofs into the pending table. Fails with `ProofAlreadyUsed` if a proof is contained twice and with
+    /// `ProofPending` if it is already reserved. A concurrent transaction with the same proofs blocks on the primary
+    /// key of Y until the first one is committed or rolled back.
+    async fn insert_pending_proofs(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        columns: &ProofColumns,
+        quote_id: Option<&Uuid>,
+    ) -> Result<(), MokshaMintError> {
+        let mut unique = HashSet::new();
+        if let Some(y) = columns.ys.iter().find(|y| !unique.insert(*y)) {
+            return Err(MokshaMintError::ProofAlreadyUsed(y.to_owned()));
+        }
+
+        let inserted = sqlx::query!(
+            r#"INSERT INTO pending_proofs (amount, secret, c, keyset_id, y, quote_id)
+            SELECT amount, secret, c, keyset_id, y, $6
+            FROM UNNEST($1::BIGINT[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[]) AS p(amount, secret, c, keyset_id, y)
+            ON CONFLICT DO NOTHING
+            RETURNING y"#,
+            &columns.amounts,
+            &columns.secrets,
+            &columns.cs,
+            &columns.keyset_ids,
+            &columns.ys,
+            quote_id
+        )
+        .fetch_all(&mut **tx)
+        .await?
+        .into_iter()
+        .map(|row| row.y)
+        .collect::<Vec<_>>();
+
+        match columns.ys.iter().find(|y| !inserted.contains(y)) {
+            Some(y) => Err(MokshaMintError::ProofPending(y.to_owned())),
+            None => Ok(()),
+        }
     }
 }
 
@@ -51,47 +174,326 @@ impl Database for PostgresDB {
     }
 
     #[instrument(level = "debug", skip(self), err)]
-    async fn get_used_proofs(
+    async fn add_used_proofs(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        proofs: &Proofs,
+    ) -> Result<(), MokshaMintError> {
+        let columns = ProofColumns::new(proofs)?;
+
+        // the proofs pass through the pending table, so a spend waits for or fails on a concurrent reservation
+        Self::insert_pending_proofs(tx, &columns, None).await?;
+
+        // rows that conflict with an already spent proof are skipped
+        let inserted = sqlx::query!(
+            r#"INSERT INTO used_proofs (amount, secret, c, keyset_id, y)
+            SELECT * FROM UNNEST($1::BIGINT[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[])
+            ON CONFLICT DO NOTHING
+            RETURNING y"#,
+            &columns.amounts,
+            &columns.secrets,
+            &columns.cs,
+            &columns.keyset_ids,
+            &columns.ys
+        )
+        .fetch_all(&mut **tx)
+        .await?
+        .into_iter()
+        .filter_map(|row| row.y)
+        .collect::<Vec<_>>();
+        if let Some(y) = columns.ys.iter().find(|y| !inserted.contains(y)) {
+            return Err(MokshaMintError::ProofAlreadyUsed(y.to_owned()));
+        }
+
+        sqlx::query!("DELETE FROM pending_proofs WHERE y = ANY($1)", &columns.ys)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_spent_ys(
         &self,
         tx: &mut sqlx::Transaction<Self::DB>,
-    ) -> Result<Proofs, MokshaMintError> {
-        let proofs = sqlx::query!("SELECT * FROM used_proofs")
+        ys: &[PublicKey],
+    ) -> Result<Vec<PublicKey>, MokshaMintError> {
+        let ys = ys.iter().map(|y| y.to_string()).collect::<Vec<_>>();
+        let rows = sqlx::query!("SELECT y FROM used_proofs WHERE y = ANY($1)", &ys)
             .fetch_all(&mut **tx)
-            .await?
+            .await?;
+
+        Ok(rows
             .into_iter()
-            .map(|row| Proof {
-                amount: row.amount as u64,
-                secret: row.secret,
-                c: dhke::public_key_from_hex(&row.c).to_owned(),
-                keyset_id: row.keyset_id,
-                script: None,
-            })
-            .collect::<Vec<Proof>>();
+            .filter_map(|row| row.y)
+            .map(|y| dhke::public_key_from_hex(&y))
+            .collect())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn add_pending_proofs(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        proofs: &Proofs,
+        quote_id: Option<&Uuid>,
+    ) -> Result<(), MokshaMintError> {
+        let columns = ProofColumns::new(proofs)?;
+        Self::insert_pending_proofs(tx, &columns, quote_id).await?;
 
-        Ok(proofs.into())
+        let spent = sqlx::query!("SELECT y FROM used_proofs WHERE y = ANY($1)", &columns.ys)
+            .fetch_all(&mut **tx)
+            .await?;
+        match spent.into_iter().find_map(|row| row.y) {
+            Some(y) => Err(MokshaMintError::ProofAlreadyUsed(y)),
+            None => Ok(()),
+        }
     }
 
     #[instrument(level = "debug", skip(self), err)]
-    async fn add_used_proofs(
+    async fn delete_pending_proofs(
         &self,
         tx: &mut sqlx::Transaction<Self::DB>,
         proofs: &Proofs,
     ) -> Result<(), MokshaMintError> {
-        for proof in proofs.proofs() {
+        let columns = ProofColumns::new(proofs)?;
+        sqlx::query!("DELETE FROM pending_proofs WHERE y = ANY($1)", &columns.ys)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_pending_proofs(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+    ) -> Result<Vec<(Uuid, Proofs)>, MokshaMintError> {
+        let rows = sqlx::query!(
+            r#"SELECT amount, secret, c, keyset_id, quote_id as "quote_id!" FROM pending_proofs
+            WHERE quote_id IS NOT NULL ORDER BY quote_id, created_at"#
+        )
+        .fetch_all(&mut **tx)
+        .await?;
+
+        let mut pending: Vec<(Uuid, Vec<Proof>)> = vec![];
+        for row in rows {
+            let proof = Proof::new(
+                row.amount as u64,
+                row.secret,
+                dhke::public_key_from_hex(&row.c),
+                row.keyset_id,
+            );
+            match pending.last_mut() {
+                Some((quote_id, proofs)) if *quote_id == row.quote_id => proofs.push(proof),
+                _ => pending.push((row.quote_id, vec![proof])),
+            }
+        }
+        Ok(pending
+            .into_iter()
+            .map(|(quote_id, proofs)| (quote_id, proofs.into()))
+            .collect())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_pending_ys(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        ys: &[PublicKey],
+    ) -> Result<Vec<PublicKey>, MokshaMintError> {
+        let ys = ys.iter().map(|y| y.to_string()).collect::<Vec<_>>();
+        let rows = sqlx::query!("SELECT y FROM pending_proofs WHERE y = ANY($1)", &ys)
+            .fetch_all(&mut **tx)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| dhke::public_key_from_hex(&row.y))
+            .collect())
+    }
+
+    #[instrument(level = "debug", skip(self, outputs, signatures), err)]
+    async fn add_blind_signatures(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        outputs: &[BlindedMessage],
+        signatures: &[BlindedSignature],
+    ) -> Result<(), MokshaMintError> {
+        for (output, signature) in outputs.iter().zip(signatures) {
             sqlx::query!(
-                "INSERT INTO used_proofs (amount, secret, c, keyset_id) VALUES ($1, $2, $3, $4)",
-                proof.amount as i64,
-                proof.secret,
-                proof.c.to_string(),
-                proof.keyset_id.to_string()
+                "INSERT INTO blind_signatures (b_, amount, c_, keyset_id, dleq_e, dleq_s) VALUES ($1, $2, $3, $4, $5, $6)",
+                output.b_.to_string(),
+                signature.amount as i64,
+                signature.c_.to_string(),
+                signature.id,
+                signature.dleq.map(|dleq| dleq.e.display_secret().to_string()),
+                signature.dleq.map(|dleq| dleq.s.display_secret().to_string())
             )
             .execute(&mut **tx)
             .await?;
         }
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self, b_s), err)]
+    async fn get_blind_signatures(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        b_s: &[PublicKey],
+    ) -> Result<Vec<(BlindedMessage, BlindedSignature)>, MokshaMintError> {
+        let b_s = b_s.iter().map(|b_| b_.to_string()).collect::<Vec<_>>();
+        let rows = sqlx::query!(
+            "SELECT b_, amount, c_, keyset_id, dleq_e, dleq_s FROM blind_signatures WHERE b_ = ANY($1)",
+            &b_s
+        )
+        .fetch_all(&mut **tx)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let dleq = match (row.dleq_e, row.dleq_s) {
+                    (Some(e), Some(s)) => Some(DleqProof {
+                        e: SecretKey::from_str(&e)?,
+                        s: SecretKey::from_str(&s)?,
+                    }),
+                    _ => None,
+                };
+                Ok((
+                    BlindedMessage {
+                        amount: row.amount as u64,
+                        b_: dhke::public_key_from_hex(&row.b_),
+                        id: row.keyset_id.clone(),
+                        witness: None,
+                    },
+                    BlindedSignature {
+                        amount: row.amount as u64,
+                        c_: dhke::public_key_from_hex(&row.c_),
+                        id: row.keyset_id,
+                        dleq,
+                    },
+                ))
+            })
+            .collect::<Result<Vec<_>, secp256k1::Error>>()
+            .map_err(|e| MokshaMintError::MokshaCore(e.into()))
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_keysets(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+    ) -> Result<Vec<KeysetInfo>, MokshaMintError> {
+        Ok(sqlx::query!(
+            "SELECT id, unit, derivation_path, active, input_fee_ppk FROM keysets ORDER BY created_at"
+        )
+        .fetch_all(&mut **tx)
+        .await?
+        .into_iter()
+        .map(|row| KeysetInfo {
+            id: row.id,
+            unit: CurrencyUnit::from(row.unit),
+            derivation_path: row.derivation_path,
+            active: row.active,
+            input_fee_ppk: row.input_fee_ppk as u64,
+        })
+        .collect())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn add_keyset(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        keyset: &KeysetInfo,
+    ) -> Result<(), MokshaMintError> {
+        sqlx::query!(
+            "INSERT INTO keysets (id, unit, derivation_path, active, input_fee_ppk) VALUES ($1, $2, $3, $4, $5)",
+            keyset.id,
+            keyset.unit.to_string(),
+            keyset.derivation_path,
+            keyset.active,
+            keyset.input_fee_ppk as i64
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn update_keyset_active(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        id: &str,
+        active: bool,
+    ) -> Result<(), MokshaMintError> {
+        sqlx::query!("UPDATE keysets SET active = $1 WHERE id = $2", active, id)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
 
+    #[instrument(level = "debug", skip(self), err)]
+    async fn add_liabilities(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        liabilities: &[Liabilities],
+    ) -> Result<(), MokshaMintError> {
+        for entry in liabilities {
+            let unit = entry.unit.to_string();
+            let (minted, melted, melt_change) = (
+                entry.minted as i64,
+                entry.melted as i64,
+                entry.melt_change as i64,
+            );
+            let (swapped_in, swapped_out, fees) = (
+                entry.swapped_in as i64,
+                entry.swapped_out as i64,
+                entry.fees as i64,
+            );
+            sqlx::query!(
+                r#"INSERT INTO liabilities (keyset_id, unit, minted, melted, melt_change, swapped_in, swapped_out, fees)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (keyset_id) DO UPDATE SET
+                minted = liabilities.minted + excluded.minted,
+                melted = liabilities.melted + excluded.melted,
+                melt_change = liabilities.melt_change + excluded.melt_change,
+                swapped_in = liabilities.swapped_in + excluded.swapped_in,
+                swapped_out = liabilities.swapped_out + excluded.swapped_out,
+                fees = liabilities.fees + excluded.fees"#,
+                entry.keyset_id,
+                unit,
+                minted,
+                melted,
+                melt_change,
+                swapped_in,
+                swapped_out,
+                fees
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
         Ok(())
     }
 
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_liabilities(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+    ) -> Result<Vec<Liabilities>, MokshaMintError> {
+        Ok(sqlx::query!(
+            "SELECT keyset_id, unit, minted, melted, melt_change, swapped_in, swapped_out, fees FROM liabilities ORDER BY created_at"
+        )
+        .fetch_all(&mut **tx)
+        .await?
+        .into_iter()
+        .map(|row| Liabilities {
+            keyset_id: row.keyset_id,
+            unit: CurrencyUnit::from(row.unit),
+            minted: row.minted as u64,
+            melted: row.melted as u64,
+            melt_change: row.melt_change as u64,
+            swapped_in: row.swapped_in as u64,
+            swapped_out: row.swapped_out as u64,
+            fees: row.fees as u64,
+        })
+        .collect())
+    }
+
     #[instrument(level = "debug", skip(self))]
     async fn get_pending_invoice(
         &self,
@@ -150,12 +552,14 @@ impl Database for PostgresDB {
         id: &Uuid,
     ) -> Result<Bolt11MintQuote, MokshaMintError> {
         let quote: Bolt11MintQuote = sqlx::query!(
-            "SELECT id, payment_request, expiry, paid FROM bolt11_mint_quotes WHERE id = $1",
+            "SELECT id, payment_request, amount, unit, expiry, paid FROM bolt11_mint_quotes WHERE id = $1",
             id
         )
         .map(|row| Bolt11MintQuote {
             quote_id: row.id,
             payment_request: row.payment_request,
+            amount: row.amount as u64,
+            unit: CurrencyUnit::from(row.unit),
             expiry: row.expiry as u64,
             paid: row.paid,
         })
@@ -164,6 +568,142 @@ impl Database for PostgresDB {
         Ok(quote)
     }
 
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_unpaid_bolt11_mint_quote_by_payment_hash(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        payment_hash: &str,
+    ) -> Result<Option<Bolt11MintQuote>, MokshaMintError> {
+        let quote = sqlx::query!(
+            "SELECT id, payment_request, amount, unit, expiry, paid FROM bolt11_mint_quotes WHERE payment_hash = $1 AND paid = false FOR UPDATE",
+            payment_hash
+        )
+        .map(|row| Bolt11MintQuote {
+            quote_id: row.id,
+            payment_request: row.payment_request,
+            amount: row.amount as u64,
+            unit: CurrencyUnit::from(row.unit),
+            expiry: row.expiry as u64,
+            paid: row.paid,
+        })
+        .fetch_optional(&mut **tx)
+        .await?;
+        Ok(quote)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_bolt11_mint_quote_by_payment_hash(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        payment_hash: &str,
+    ) -> Result<Option<Bolt11MintQuote>, MokshaMintError> {
+        let quote = sqlx::query!(
+            "SELECT id, payment_request, amount, unit, expiry, paid FROM bolt11_mint_quotes WHERE payment_hash = $1",
+            payment_hash
+        )
+        .map(|row| Bolt11MintQuote {
+            quote_id: row.id,
+            payment_request: row.payment_request,
+            amount: row.amount as u64,
+            unit: CurrencyUnit::from(row.unit),
+            expiry: row.expiry as u64,
+            paid: row.paid,
+        })
+        .fetch_optional(&mut **tx)
+        .await?;
+        Ok(quote)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_unpaid_bolt11_mint_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+    ) -> Result<Vec<Bolt11MintQuote>, MokshaMintError> {
+        let quotes = sqlx::query!(
+            "SELECT id, payment_request, amount, unit, expiry, paid FROM bolt11_mint_quotes WHERE paid = false"
+        )
+        .map(|row| Bolt11MintQuote {
+            quote_id: row.id,
+            payment_request: row.payment_request,
+            amount: row.amount as u64,
+            unit: CurrencyUnit::from(row.unit),
+            expiry: row.expiry as u64,
+            paid: row.paid,
+        })
+        .fetch_all(&mut **tx)
+        .await?;
+        Ok(quotes)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn search_bolt11_mint_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        filter: &MintQuoteFilter,
+    ) -> Result<Vec<Bolt11MintQuote>, MokshaMintError> {
+        let (id, search) = search_term(filter.search.as_deref());
+        let quotes = sqlx::query!(
+            "SELECT id, payment_request, amount, unit, expiry, paid FROM bolt11_mint_quotes WHERE ($1::UUID IS NULL OR id = $1) AND ($2::TEXT IS NULL OR payment_hash = $2 OR payment_request LIKE '%' || $2 || '%') AND ($3::TEXT IS NULL OR unit = $3) AND ($4::BOOL IS NULL OR paid = $4) ORDER BY expiry DESC, id LIMIT $5 OFFSET $6",
+            id,
+            search,
+            filter.unit.as_ref().map(ToString::to_string),
+            filter.paid,
+            filter.limit.unwrap_or(DEFAULT_SEARCH_LIMIT) as i64,
+            filter.offset.unwrap_or_default() as i64
+        )
+        .map(|row| Bolt11MintQuote {
+            quote_id: row.id,
+            payment_request: row.payment_request,
+            amount: row.amount as u64,
+            unit: CurrencyUnit::from(row.unit),
+            expiry: row.expiry as u64,
+            paid: row.paid,
+        })
+        .fetch_all(&mut **tx)
+        .await?;
+        Ok(quotes)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn update_bolt11_mint_quote_expiry(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+        expiry: u64,
+    ) -> Result<bool, MokshaMintError> {
+        let result = sqlx::query!(
+            "UPDATE bolt11_mint_quotes SET expiry = $1 WHERE id = $2 AND paid = false",
+            expiry as i64,
+            key
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_expired_bolt11_mint_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        expired_before: u64,
+    ) -> Result<Vec<Bolt11MintQuote>, MokshaMintError> {
+        let quotes = sqlx::query!(
+            "SELECT id, payment_request, amount, unit, expiry, paid FROM bolt11_mint_quotes WHERE paid = false AND expiry < $1",
+            expired_before as i64
+        )
+        .map(|row| Bolt11MintQuote {
+            quote_id: row.id,
+            payment_request: row.payment_request,
+            amount: row.amount as u64,
+            unit: CurrencyUnit::from(row.unit),
+            expiry: row.expiry as u64,
+            paid: row.paid,
+        })
+        .fetch_all(&mut **tx)
+        .await?;
+        Ok(quotes)
+    }
+
     #[instrument(level = "debug", skip(self), err)]
     async fn add_bolt11_mint_quote(
         &self,
@@ -171,9 +711,12 @@ impl Database for PostgresDB {
         quote: &Bolt11MintQuote,
     ) -> Result<(), MokshaMintError> {
         sqlx::query!(
-            "INSERT INTO bolt11_mint_quotes (id, payment_request, expiry, paid) VALUES ($1, $2, $3, $4)",
+            "INSERT INTO bolt11_mint_quotes (id, payment_request, payment_hash, amount, unit, expiry, paid) VALUES ($1, $2, $3, $4, $5, $6, $7)",
             quote.quote_id,
             quote.payment_request,
+            payment_hash(&quote.payment_request),
+            quote.amount as i64,
+            quote.unit.to_string(),
             quote.expiry as i64,
             quote.paid
         )
@@ -198,6 +741,21 @@ impl Database for PostgresDB {
         Ok(())
     }
 
+    #[instrument(level = "debug", skip(self), err)]
+    async fn mark_bolt11_mint_quote_paid(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+    ) -> Result<bool, MokshaMintError> {
+        let result = sqlx::query!(
+            "UPDATE bolt11_mint_quotes SET paid = true WHERE id = $1 AND paid = false",
+            key
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
     #[instrument(level = "debug", skip(self), err)]
     async fn delete_bolt11_mint_quote(
         &self,
@@ -220,16 +778,19 @@ impl Database for PostgresDB {
         key: &Uuid,
     ) -> Result<Bolt11MeltQuote, MokshaMintError> {
         let quote: Bolt11MeltQuote = sqlx::query!(
-            "SELECT id, payment_request, expiry, paid, amount, fee_reserve FROM bolt11_melt_quotes WHERE id = $1",
+            "SELECT id, payment_request, expiry, state, amount, fee_reserve, unit, payment_preimage, fee_paid FROM bolt11_melt_quotes WHERE id = $1",
             key
         )
         .map(|row| Bolt11MeltQuote {
             quote_id: row.id,
             payment_request: row.payment_request,
             expiry: row.expiry as u64,
-            paid: row.paid,
+            state: MeltQuoteState::from_str(&row.state).expect("invalid state in melt quote"),
             amount: row.amount as u64,
             fee_reserve: row.fee_reserve as u64,
+            unit: CurrencyUnit::from(row.unit),
+            payment_preimage: row.payment_preimage,
+            fee_paid: row.fee_paid.map(|fee_paid| fee_paid as u64),
         })
         .fetch_one(&mut **tx)
         .await?;
@@ -244,13 +805,16 @@ impl Database for PostgresDB {
         quote: &Bolt11MeltQuote,
     ) -> Result<(), MokshaMintError> {
         sqlx::query!(
-            "INSERT INTO bolt11_melt_quotes (id, payment_request, expiry, paid, amount, fee_reserve) VALUES ($1, $2, $3, $4, $5, $6)",
+            "INSERT INTO bolt11_melt_quotes (id, payment_request, expiry, state, amount, fee_reserve, unit, payment_preimage, fee_paid) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
             quote.quote_id,
             quote.payment_request,
             quote.expiry as i64,
-            quote.paid,
+            quote.state.to_string(),
             quote.amount as i64,
-            quote.fee_reserve as i64
+            quote.fee_reserve as i64,
+            quote.unit.to_string(),
+            quote.payment_preimage,
+            quote.fee_paid.map(|fee_paid| fee_paid as i64)
         )
         .execute(&mut **tx)
         .await?;
@@ -264,8 +828,10 @@ impl Database for PostgresDB {
         quote: &Bolt11MeltQuote,
     ) -> Result<(), MokshaMintError> {
         sqlx::query!(
-            "UPDATE bolt11_melt_quotes SET paid = $1 WHERE id = $2",
-            quote.paid,
+            "UPDATE bolt11_melt_quotes SET state = $1, payment_preimage = $2, fee_paid = $3 WHERE id = $4",
+            quote.state.to_string(),
+            quote.payment_preimage,
+            quote.fee_paid.map(|fee_paid| fee_paid as i64),
             quote.quote_id
         )
         .execute(&mut **tx)
@@ -273,6 +839,124 @@ impl Database for PostgresDB {
         Ok(())
     }
 
+    #[instrument(level = "debug", skip(self), err)]
+    async fn search_bolt11_melt_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        filter: &MeltQuoteFilter,
+    ) -> Result<Vec<Bolt11MeltQuote>, MokshaMintError> {
+        let (id, search) = search_term(filter.search.as_deref());
+        let quotes = sqlx::query!(
+            "SELECT id, payment_request, expiry, state, amount, fee_reserve, unit, payment_preimage, fee_paid FROM bolt11_melt_quotes WHERE ($1::UUID IS NULL OR id = $1) AND ($2::TEXT IS NULL OR payment_request LIKE '%' || $2 || '%') AND ($3::TEXT IS NULL OR unit = $3) AND ($4::TEXT IS NULL OR state = $4) ORDER BY expiry DESC, id LIMIT $5 OFFSET $6",
+            id,
+            search,
+            filter.unit.as_ref().map(ToString::to_string),
+            filter.state.as_ref().map(ToString::to_string),
+            filter.limit.unwrap_or(DEFAULT_SEARCH_LIMIT) as i64,
+            filter.offset.unwrap_or_default() as i64
+        )
+        .map(|row| Bolt11MeltQuote {
+            quote_id: row.id,
+            payment_request: row.payment_request,
+            expiry: row.expiry as u64,
+            state: MeltQuoteState::from_str(&row.state).expect("invalid state in melt quote"),
+            amount: row.amount as u64,
+            fee_reserve: row.fee_reserve as u64,
+            unit: CurrencyUnit::from(row.unit),
+            payment_preimage: row.payment_preimage,
+            fee_paid: row.fee_paid.map(|fee_paid| fee_paid as u64),
+        })
+        .fetch_all(&mut **tx)
+        .await?;
+        Ok(quotes)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn update_bolt11_melt_quote_expiry(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+        expiry: u64,
+    ) -> Result<bool, MokshaMintError> {
+        let result = sqlx::query!(
+            "UPDATE bolt11_melt_quotes SET expiry = $1 WHERE id = $2 AND state = $3",
+            expiry as i64,
+            key,
+            MeltQuoteState::Unpaid.to_string()
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn transition_bolt11_melt_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+        from: &MeltQuoteState,
+        to: &MeltQuoteState,
+    ) -> Result<bool, MokshaMintError> {
+        let result = sqlx::query!(
+            "UPDATE bolt11_melt_quotes SET state = $1 WHERE id = $2 AND state = $3",
+            to.to_string(),
+            key,
+            from.to_string()
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn set_bolt11_melt_quote_payment_id(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+        payment_id: &str,
+    ) -> Result<(), MokshaMintError> {
+        sqlx::query!(
+            "UPDATE bolt11_melt_quotes SET payment_id = $1 WHERE id = $2",
+            payment_id,
+            key
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_bolt11_melt_quote_payment_id(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+    ) -> Result<Option<String>, MokshaMintError> {
+        let payment_id = sqlx::query!(
+            "SELECT payment_id FROM bolt11_melt_quotes WHERE id = $1",
+            key
+        )
+        .map(|row| row.payment_id)
+        .fetch_one(&mut **tx)
+        .await?;
+        Ok(payment_id)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn delete_expired_bolt11_melt_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        expired_before: u64,
+    ) -> Result<u64, MokshaMintError> {
+        let result = sqlx::query!(
+            "DELETE FROM bolt11_melt_quotes WHERE state = $1 AND expiry < $2 AND NOT EXISTS (SELECT 1 FROM pending_proofs WHERE pending_proofs.quote_id = bolt11_melt_quotes.id)",
+            MeltQuoteState::Unpaid.to_string(),
+            expired_before as i64
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
     #[instrument(level = "debug", skip(self), err)]
     async fn delete_bolt11_melt_quote(
         &self,
@@ -312,6 +996,30 @@ impl Database for PostgresDB {
         Ok(quote)
     }
 
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_expired_onchain_mint_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        expired_before: u64,
+    ) -> Result<Vec<BtcOnchainMintQuote>, MokshaMintError> {
+        let quotes = sqlx::query!(
+            "SELECT id, address, amount, expiry, state FROM onchain_mint_quotes WHERE state = $1 AND expiry < $2",
+            MintBtcOnchainState::Unpaid.to_string(),
+            expired_before as i64
+        )
+        .map(|row| BtcOnchainMintQuote {
+            quote_id: row.id,
+            address: row.address,
+            expiry: row.expiry as u64,
+            state: MintBtcOnchainState::from_str(&row.state).expect("invalid state in mint quote"),
+            amount: row.amount as u64,
+            unit: CurrencyUnit::Sat,
+        })
+        .fetch_all(&mut **tx)
+        .await?;
+        Ok(quotes)
+    }
+
     #[instrument(level = "debug", skip(self), err)]
     async fn add_onchain_mint_quote(
         &self,
@@ -426,6 +1134,22 @@ impl Database for PostgresDB {
         Ok(())
     }
 
+    #[instrument(level = "debug", skip(self), err)]
+    async fn delete_expired_onchain_melt_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        expired_before: u64,
+    ) -> Result<u64, MokshaMintError> {
+        let result = sqlx::query!(
+            "DELETE FROM onchain_melt_quotes WHERE state = $1 AND expiry < $2 AND NOT EXISTS (SELECT 1 FROM pending_proofs WHERE pending_proofs.quote_id = onchain_melt_quotes.id)",
+            MeltBtcOnchainState::Unpaid.to_string(),
+            expired_before as i64
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
     #[instrument(level = "debug", skip(self), err)]
     async fn delete_onchain_melt_quote(
         &self,
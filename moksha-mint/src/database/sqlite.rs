@@ -0,0 +1,1196 @@
+#![allow(clippy::blocks_in_conditions)]
+use std::{collections::HashSet, str::FromStr};
+
+use async_trait::async_trait;
+use moksha_core::{
+    blind::{BlindedMessage, BlindedSignature, DleqProof},
+    dhke::{self, Dhke},
+    primitives::{
+        Bolt11MeltQuote, Bolt11MintQuote, BtcOnchainMeltQuote, BtcOnchainMintQuote, CurrencyUnit,
+        MeltBtcOnchainState, MeltQuoteState, MintBtcOnchainState,
+    },
+    proof::{Proof, Proofs},
+};
+
+use secp256k1::{PublicKey, SecretKey};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    config::DatabaseConfig,
+    error::MokshaMintError,
+    model::{Invoice, KeysetInfo, Liabilities, MeltQuoteFilter, MintQuoteFilter},
+};
+
+use super::{payment_hash, search_term, Database, DEFAULT_SEARCH_LIMIT};
+
+/// A mint database in a single sqlite file for small deployments.
+///
+/// The connections share one cache and read uncommitted rows, so a transaction that is still open while the mint
+/// commits another transaction (e.g. the reservation of the proofs of a melt) can write afterwards. Writes are
+/// serialized, a concurrent write waits until the writing transaction is committed or rolled back.
+#[derive(Clone)]
+pub struct SqliteDB {
+    pool: sqlx::Pool<sqlx::Sqlite>,
+}
+
+impl SqliteDB {
+    pub async fn new(config: &DatabaseConfig) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(&config.db_url)?
+            .create_if_missing(true)
+            .shared_cache(true)
+            .pragma("read_uncommitted", "true");
+        Ok(Self {
+            pool: SqlitePoolOptions::new()
+                .max_connections(config.max_connections)
+                .connect_with(options)
+                .await?,
+        })
+    }
+
+    /// Creates a migrated database that only lives in memory, e.g. for tests. Every call creates a new empty
+    /// database, that is dropped together with the last clone.
+    pub async fn in_memory() -> Result<Self, sqlx::Error> {
+        let options =
+            SqliteConnectOptions::from_str("sqlite::memory:")?.pragma("read_uncommitted", "true");
+        // the database is dropped when its last connection is closed, so one connection is kept open
+        let db = Self {
+            pool: SqlitePoolOptions::new()
+                .min_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .connect_with(options)
+                .await?,
+        };
+        sqlx::migrate!("./migrations_sqlite").run(&db.pool).await?;
+        Ok(db)
+    }
+
+    pub async fn migrate(&self) {
+        sqlx::migrate!("./migrations_sqlite")
+            .run(&self.pool)
+            .await
+            .expect("Could not run migrations");
+    }
+}
+
+/// the Y values of the proofs as hex
+fn proof_ys(proofs: &Proofs) -> Result<Vec<String>, MokshaMintError> {
+    proofs
+        .proofs()
+        .iter()
+        .map(|proof| Ok(Dhke::hash_to_curve(proof.secret.as_bytes())?.to_string()))
+        .collect()
+}
+
+/// sqlite has no arrays, so lists of values are passed as a json array and expanded with json_each
+fn to_json_array(values: &[String]) -> Result<String, MokshaMintError> {
+    serde_json::to_string(values)
+        .map_err(|err| MokshaMintError::Db(sqlx::Error::Encode(err.into())))
+}
+
+impl SqliteDB {
+    /// Inserts the proofs into the pending table. Fails with `ProofAlreadyUsed` if a proof is contained twice and with
+    /// `ProofPending` if it is already reserved.
+    async fn insert_pending_proofs(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        proofs: &Proofs,
+        ys: &[String],
+        quote_id: Option<&Uuid>,
+    ) -> Result<(), MokshaMintError> {
+        let mut unique = HashSet::new();
+        if let Some(y) = ys.iter().find(|y| !unique.insert(*y)) {
+            return Err(MokshaMintError::ProofAlreadyUsed(y.to_owned()));
+        }
+
+        for (proof, y) in proofs.proofs().iter().zip(ys) {
+            let amount = proof.amount as i64;
+            let c = proof.c.to_string();
+            let inserted = sqlx::query!(
+                "INSERT INTO pending_proofs (amount, secret, c, keyset_id, y, quote_id) VALUES (?, ?, ?, ?, ?, ?) ON CONFLICT DO NOTHING",
+                amount,
+                proof.secret,
+                c,
+                proof.keyset_id,
+                y,
+                quote_id
+            )
+            .execute(&mut **tx)
+            .await?;
+            if inserted.rows_affected() == 0 {
+                return Err(MokshaMintError::ProofPending(y.to_owned()));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Database for SqliteDB {
+    type DB = sqlx::Sqlite;
+
+    async fn begin_tx(&self) -> Result<sqlx::Transaction<Self::DB>, sqlx::Error> {
+        self.pool.begin().await
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn add_used_proofs(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        proofs: &Proofs,
+    ) -> Result<(), MokshaMintError> {
+        let ys = proof_ys(proofs)?;
+
+        // the proofs pass through the pending table, so a spend fails on a reservation of a melt
+        Self::insert_pending_proofs(tx, proofs, &ys, None).await?;
+
+        for (proof, y) in proofs.proofs().iter().zip(&ys) {
+            let amount = proof.amount as i64;
+            let c = proof.c.to_string();
+            let inserted = sqlx::query!(
+                "INSERT INTO used_proofs (amount, secret, c, keyset_id, y) VALUES (?, ?, ?, ?, ?) ON CONFLICT DO NOTHING",
+                amount,
+                proof.secret,
+                c,
+                proof.keyset_id,
+                y
+            )
+            .execute(&mut **tx)
+            .await?;
+            if inserted.rows_affected() == 0 {
+                return Err(MokshaMintError::ProofAlreadyUsed(y.to_owned()));
+            }
+        }
+
+        let ys = to_json_array(&ys)?;
+        sqlx::query!(
+            "DELETE FROM pending_proofs WHERE y IN (SELECT value FROM json_each(?))",
+            ys
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_spent_ys(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        ys: &[PublicKey],
+    ) -> Result<Vec<PublicKey>, MokshaMintError> {
+        let ys = to_json_array(&ys.iter().map(|y| y.to_string()).collect::<Vec<_>>())?;
+        let rows = sqlx::query!(
+            "SELECT y FROM used_proofs WHERE y IN (SELECT value FROM json_each(?))",
+            ys
+        )
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| dhke::public_key_from_hex(&row.y))
+            .collect())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn add_pending_proofs(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        proofs: &Proofs,
+        quote_id: Option<&Uuid>,
+    ) -> Result<(), MokshaMintError> {
+        let ys = proof_ys(proofs)?;
+        Self::insert_pending_proofs(tx, proofs, &ys, quote_id).await?;
+
+        let ys = to_json_array(&ys)?;
+        let spent = sqlx::query!(
+            "SELECT y FROM used_proofs WHERE y IN (SELECT value FROM json_each(?)) LIMIT 1",
+            ys
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+        match spent {
+            Some(row) => Err(MokshaMintError::ProofAlreadyUsed(row.y)),
+            None => Ok(()),
+        }
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn delete_pending_proofs(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        proofs: &Proofs,
+    ) -> Result<(), MokshaMintError> {
+        let ys = to_json_array(&proof_ys(proofs)?)?;
+        sqlx::query!(
+            "DELETE FROM pending_proofs WHERE y IN (SELECT value FROM json_each(?))",
+            ys
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_pending_proofs(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+    ) -> Result<Vec<(Uuid, Proofs)>, MokshaMintError> {
+        let rows = sqlx::query!(
+            r#"SELECT amount, secret, c, keyset_id, quote_id as "quote_id!: Uuid" FROM pending_proofs
+            WHERE quote_id IS NOT NULL ORDER BY quote_id, rowid"#
+        )
+        .fetch_all(&mut **tx)
+        .await?;
+
+        let mut pending: Vec<(Uuid, Vec<Proof>)> = vec![];
+        for row in rows {
+            let proof = Proof::new(
+                row.amount as u64,
+                row.secret,
+                dhke::public_key_from_hex(&row.c),
+                row.keyset_id,
+            );
+            match pending.last_mut() {
+                Some((quote_id, proofs)) if *quote_id == row.quote_id => proofs.push(proof),
+                _ => pending.push((row.quote_id, vec![proof])),
+            }
+        }
+        Ok(pending
+            .into_iter()
+            .map(|(quote_id, proofs)| (quote_id, proofs.into()))
+            .collect())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_pending_ys(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        ys: &[PublicKey],
+    ) -> Result<Vec<PublicKey>, MokshaMintError> {
+        let ys = to_json_array(&ys.iter().map(|y| y.to_string()).collect::<Vec<_>>())?;
+        let rows = sqlx::query!(
+            "SELECT y FROM pending_proofs WHERE y IN (SELECT value FROM json_each(?))",
+            ys
+        )
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| dhke::public_key_from_hex(&row.y))
+            .collect())
+    }
+
+    #[instrument(level = "debug", skip(self, outputs, signatures), err)]
+    async fn add_blind_signatures(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        outputs: &[BlindedMessage],
+        signatures: &[BlindedSignature],
+    ) -> Result<(), MokshaMintError> {
+        for (output, signature) in outputs.iter().zip(signatures) {
+            let b_ = output.b_.to_string();
+            let amount = signature.amount as i64;
+            let c_ = signature.c_.to_string();
+            let dleq_e = signature
+                .dleq
+                .map(|dleq| dleq.e.display_secret().to_string());
+            let dleq_s = signature
+                .dleq
+                .map(|dleq| dleq.s.display_secret().to_string());
+            sqlx::query!(
+                "INSERT INTO blind_signatures (b_, amount, c_, keyset_id, dleq_e, dleq_s) VALUES (?, ?, ?, ?, ?, ?)",
+                b_,
+                amount,
+                c_,
+                signature.id,
+                dleq_e,
+                dleq_s
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self, b_s), err)]
+    async fn get_blind_signatures(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        b_s: &[PublicKey],
+    ) -> Result<Vec<(BlindedMessage, BlindedSignature)>, MokshaMintError> {
+        let b_s = to_json_array(&b_s.iter().map(|b_| b_.to_string()).collect::<Vec<_>>())?;
+        let rows = sqlx::query!(
+            "SELECT b_, amount, c_, keyset_id, dleq_e, dleq_s FROM blind_signatures WHERE b_ IN (SELECT value FROM json_each(?))",
+            b_s
+        )
+        .fetch_all(&mut **tx)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let dleq = match (row.dleq_e, row.dleq_s) {
+                    (Some(e), Some(s)) => Some(DleqProof {
+                        e: SecretKey::from_str(&e)?,
+                        s: SecretKey::from_str(&s)?,
+                    }),
+                    _ => None,
+                };
+                Ok((
+                    BlindedMessage {
+                        amount: row.amount as u64,
+                        b_: dhke::public_key_from_hex(&row.b_),
+                        id: row.keyset_id.clone(),
+                        witness: None,
+                    },
+                    BlindedSignature {
+                        amount: row.amount as u64,
+                        c_: dhke::public_key_from_hex(&row.c_),
+                        id: row.keyset_id,
+                        dleq,
+                    },
+                ))
+            })
+            .collect::<Result<Vec<_>, secp256k1::Error>>()
+            .map_err(|e| MokshaMintError::MokshaCore(e.into()))
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_keysets(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+    ) -> Result<Vec<KeysetInfo>, MokshaMintError> {
+        Ok(sqlx::query!(
+            "SELECT id, unit, derivation_path, active, input_fee_ppk FROM keysets ORDER BY created_at, rowid"
+        )
+        .fetch_all(&mut **tx)
+        .await?
+        .into_iter()
+        .map(|row| KeysetInfo {
+            id: row.id,
+            unit: CurrencyUnit::from(row.unit),
+            derivation_path: row.derivation_path,
+            active: row.active,
+            input_fee_ppk: row.input_fee_ppk as u64,
+        })
+        .collect())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn add_keyset(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        keyset: &KeysetInfo,
+    ) -> Result<(), MokshaMintError> {
+        let unit = keyset.unit.to_string();
+        let input_fee_ppk = keyset.input_fee_ppk as i64;
+        sqlx::query!(
+            "INSERT INTO keysets (id, unit, derivation_path, active, input_fee_ppk) VALUES (?, ?, ?, ?, ?)",
+            keyset.id,
+            unit,
+            keyset.derivation_path,
+            keyset.active,
+            input_fee_ppk
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn update_keyset_active(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        id: &str,
+        active: bool,
+    ) -> Result<(), MokshaMintError> {
+        sqlx::query!("UPDATE keysets SET active = ? WHERE id = ?", active, id)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn add_liabilities(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        liabilities: &[Liabilities],
+    ) -> Result<(), MokshaMintError> {
+        for entry in liabilities {
+            let unit = entry.unit.to_string();
+            let (minted, melted, melt_change) = (
+                entry.minted as i64,
+                entry.melted as i64,
+                entry.melt_change as i64,
+            );
+            let (swapped_in, swapped_out, fees) = (
+                entry.swapped_in as i64,
+                entry.swapped_out as i64,
+                entry.fees as i64,
+            );
+            sqlx::query!(
+                r#"INSERT INTO liabilities (keyset_id, unit, minted, melted, melt_change, swapped_in, swapped_out, fees)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (keyset_id) DO UPDATE SET
+                minted = liabilities.minted + excluded.minted,
+                melted = liabilities.melted + excluded.melted,
+                melt_change = liabilities.melt_change + excluded.melt_change,
+                swapped_in = liabilities.swapped_in + excluded.swapped_in,
+                swapped_out = liabilities.swapped_out + excluded.swapped_out,
+                fees = liabilities.fees + excluded.fees"#,
+                entry.keyset_id,
+                unit,
+                minted,
+                melted,
+                melt_change,
+                swapped_in,
+                swapped_out,
+                fees
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_liabilities(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+    ) -> Result<Vec<Liabilities>, MokshaMintError> {
+        Ok(sqlx::query!(
+            "SELECT keyset_id, unit, minted, melted, melt_change, swapped_in, swapped_out, fees FROM liabilities ORDER BY created_at, rowid"
+        )
+        .fetch_all(&mut **tx)
+        .await?
+        .into_iter()
+        .map(|row| Liabilities {
+            keyset_id: row.keyset_id,
+            unit: CurrencyUnit::from(row.unit),
+            minted: row.minted as u64,
+            melted: row.melted as u64,
+            melt_change: row.melt_change as u64,
+            swapped_in: row.swapped_in as u64,
+            swapped_out: row.swapped_out as u64,
+            fees: row.fees as u64,
+        })
+        .collect())
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn get_pending_invoice(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: String,
+    ) -> Result<Invoice, MokshaMintError> {
+        let invoice: Invoice = sqlx::query!(
+            "SELECT amount, payment_request FROM pending_invoices WHERE key = ?",
+            key
+        )
+        .map(|row| Invoice {
+            amount: row.amount as u64,
+            payment_request: row.payment_request,
+        })
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(invoice)
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn add_pending_invoice(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: String,
+        invoice: &Invoice,
+    ) -> Result<(), MokshaMintError> {
+        let amount = invoice.amount as i64;
+        sqlx::query!(
+            "INSERT INTO pending_invoices (key, amount, payment_request) VALUES (?, ?, ?)",
+            key,
+            amount,
+            invoice.payment_request
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn delete_pending_invoice(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: String,
+    ) -> Result<(), MokshaMintError> {
+        sqlx::query!("DELETE FROM pending_invoices WHERE key = ?", key)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_bolt11_mint_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        id: &Uuid,
+    ) -> Result<Bolt11MintQuote, MokshaMintError> {
+        let quote: Bolt11MintQuote = sqlx::query!(
+            r#"SELECT id as "id: Uuid", payment_request, amount, unit, expiry, paid FROM bolt11_mint_quotes WHERE id = ?"#,
+            id
+        )
+        .map(|row| Bolt11MintQuote {
+            quote_id: row.id,
+            payment_request: row.payment_request,
+            amount: row.amount as u64,
+            unit: CurrencyUnit::from(row.unit),
+            expiry: row.expiry as u64,
+            paid: row.paid,
+        })
+        .fetch_one(&mut **tx)
+        .await?;
+        Ok(quote)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_unpaid_bolt11_mint_quote_by_payment_hash(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        payment_hash: &str,
+    ) -> Result<Option<Bolt11MintQuote>, MokshaMintError> {
+        // sqlite has no row locks, the melt quote transition and the update of the mint quote are serialized by the
+        // write lock of the database
+        let quote = sqlx::query!(
+            r#"SELECT id as "id: Uuid", payment_request, amount, unit, expiry, paid FROM bolt11_mint_quotes WHERE payment_hash = ? AND paid = false"#,
+            payment_hash
+        )
+        .map(|row| Bolt11MintQuote {
+            quote_id: row.id,
+            payment_request: row.payment_request,
+            amount: row.amount as u64,
+            unit: CurrencyUnit::from(row.unit),
+            expiry: row.expiry as u64,
+            paid: row.paid,
+        })
+        .fetch_optional(&mut **tx)
+        .await?;
+        Ok(quote)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_bolt11_mint_quote_by_payment_hash(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        payment_hash: &str,
+    ) -> Result<Option<Bolt11MintQuote>, MokshaMintError> {
+        let quote = sqlx::query!(
+            r#"SELECT id as "id: Uuid", payment_request, amount, unit, expiry, paid FROM bolt11_mint_quotes WHERE payment_hash = ?"#,
+            payment_hash
+        )
+        .map(|row| Bolt11MintQuote {
+            quote_id: row.id,
+            payment_request: row.payment_request,
+            amount: row.amount as u64,
+            unit: CurrencyUnit::from(row.unit),
+            expiry: row.expiry as u64,
+            paid: row.paid,
+        })
+        .fetch_optional(&mut **tx)
+        .await?;
+        Ok(quote)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_unpaid_bolt11_mint_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+    ) -> Result<Vec<Bolt11MintQuote>, MokshaMintError> {
+        let quotes = sqlx::query!(
+            r#"SELECT id as "id: Uuid", payment_request, amount, unit, expiry, paid FROM bolt11_mint_quotes WHERE paid = false"#
+        )
+        .map(|row| Bolt11MintQuote {
+            quote_id: row.id,
+            payment_request: row.payment_request,
+            amount: row.amount as u64,
+            unit: CurrencyUnit::from(row.unit),
+            expiry: row.expiry as u64,
+            paid: row.paid,
+        })
+        .fetch_all(&mut **tx)
+        .await?;
+        Ok(quotes)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn search_bolt11_mint_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        filter: &MintQuoteFilter,
+    ) -> Result<Vec<Bolt11MintQuote>, MokshaMintError> {
+        let (id, search) = search_term(filter.search.as_deref());
+        let unit = filter.unit.as_ref().map(ToString::to_string);
+        let limit = filter.limit.unwrap_or(DEFAULT_SEARCH_LIMIT) as i64;
+        let offset = filter.offset.unwrap_or_default() as i64;
+        let quotes = sqlx::query!(
+            r#"SELECT id as "id: Uuid", payment_request, amount, unit, expiry, paid FROM bolt11_mint_quotes WHERE (?1 IS NULL OR id = ?1) AND (?2 IS NULL OR payment_hash = ?2 OR payment_request LIKE '%' || ?2 || '%') AND (?3 IS NULL OR unit = ?3) AND (?4 IS NULL OR paid = ?4) ORDER BY expiry DESC, id LIMIT ?5 OFFSET ?6"#,
+            id,
+            search,
+            unit,
+            filter.paid,
+            limit,
+            offset
+        )
+        .map(|row| Bolt11MintQuote {
+            quote_id: row.id,
+            payment_request: row.payment_request,
+            amount: row.amount as u64,
+            unit: CurrencyUnit::from(row.unit),
+            expiry: row.expiry as u64,
+            paid: row.paid,
+        })
+        .fetch_all(&mut **tx)
+        .await?;
+        Ok(quotes)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn update_bolt11_mint_quote_expiry(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+        expiry: u64,
+    ) -> Result<bool, MokshaMintError> {
+        let expiry = expiry as i64;
+        let result = sqlx::query!(
+            "UPDATE bolt11_mint_quotes SET expiry = ? WHERE id = ? AND paid = false",
+            expiry,
+            key
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_expired_bolt11_mint_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        expired_before: u64,
+    ) -> Result<Vec<Bolt11MintQuote>, MokshaMintError> {
+        let expired_before = expired_before as i64;
+        let quotes = sqlx::query!(
+            r#"SELECT id as "id: Uuid", payment_request, amount, unit, expiry, paid FROM bolt11_mint_quotes WHERE paid = false AND expiry < ?"#,
+            expired_before
+        )
+        .map(|row| Bolt11MintQuote {
+            quote_id: row.id,
+            payment_request: row.payment_request,
+            amount: row.amount as u64,
+            unit: CurrencyUnit::from(row.unit),
+            expiry: row.expiry as u64,
+            paid: row.paid,
+        })
+        .fetch_all(&mut **tx)
+        .await?;
+        Ok(quotes)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn add_bolt11_mint_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        quote: &Bolt11MintQuote,
+    ) -> Result<(), MokshaMintError> {
+        let payment_hash = payment_hash(&quote.payment_request);
+        let amount = quote.amount as i64;
+        let unit = quote.unit.to_string();
+        let expiry = quote.expiry as i64;
+        sqlx::query!(
+            "INSERT INTO bolt11_mint_quotes (id, payment_request, payment_hash, amount, unit, expiry, paid) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            quote.quote_id,
+            quote.payment_request,
+            payment_hash,
+            amount,
+            unit,
+            expiry,
+            quote.paid
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn update_bolt11_mint_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        quote: &Bolt11MintQuote,
+    ) -> Result<(), MokshaMintError> {
+        sqlx::query!(
+            "UPDATE bolt11_mint_quotes SET paid = ? WHERE id = ?",
+            quote.paid,
+            quote.quote_id
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn mark_bolt11_mint_quote_paid(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+    ) -> Result<bool, MokshaMintError> {
+        let result = sqlx::query!(
+            "UPDATE bolt11_mint_quotes SET paid = true WHERE id = ? AND paid = false",
+            key
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn delete_bolt11_mint_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        quote: &Bolt11MintQuote,
+    ) -> Result<(), MokshaMintError> {
+        sqlx::query!(
+            "DELETE FROM bolt11_mint_quotes WHERE id = ?",
+            quote.quote_id
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_bolt11_melt_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+    ) -> Result<Bolt11MeltQuote, MokshaMintError> {
+        let quote: Bolt11MeltQuote = sqlx::query!(
+            r#"SELECT id as "id: Uuid", payment_request, expiry, state, amount, fee_reserve, unit, payment_preimage, fee_paid FROM bolt11_melt_quotes WHERE id = ?"#,
+            key
+        )
+        .map(|row| Bolt11MeltQuote {
+            quote_id: row.id,
+            payment_request: row.payment_request,
+            expiry: row.expiry as u64,
+            state: MeltQuoteState::from_str(&row.state).expect("invalid state in melt quote"),
+            amount: row.amount as u64,
+            fee_reserve: row.fee_reserve as u64,
+            unit: CurrencyUnit::from(row.unit),
+            payment_preimage: row.payment_preimage,
+            fee_paid: row.fee_paid.map(|fee_paid| fee_paid as u64),
+        })
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(quote)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn add_bolt11_melt_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        quote: &Bolt11MeltQuote,
+    ) -> Result<(), MokshaMintError> {
+        let expiry = quote.expiry as i64;
+        let state = quote.state.to_string();
+        let amount = quote.amount as i64;
+        let fee_reserve = quote.fee_reserve as i64;
+        let unit = quote.unit.to_string();
+        let fee_paid = quote.fee_paid.map(|fee_paid| fee_paid as i64);
+        sqlx::query!(
+            "INSERT INTO bolt11_melt_quotes (id, payment_request, expiry, state, amount, fee_reserve, unit, payment_preimage, fee_paid) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            quote.quote_id,
+            quote.payment_request,
+            expiry,
+            state,
+            amount,
+            fee_reserve,
+            unit,
+            quote.payment_preimage,
+            fee_paid
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn update_bolt11_melt_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        quote: &Bolt11MeltQuote,
+    ) -> Result<(), MokshaMintError> {
+        let state = quote.state.to_string();
+        let fee_paid = quote.fee_paid.map(|fee_paid| fee_paid as i64);
+        sqlx::query!(
+            "UPDATE bolt11_melt_quotes SET state = ?, payment_preimage = ?, fee_paid = ? WHERE id = ?",
+            state,
+            quote.payment_preimage,
+            fee_paid,
+            quote.quote_id
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn search_bolt11_melt_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        filter: &MeltQuoteFilter,
+    ) -> Result<Vec<Bolt11MeltQuote>, MokshaMintError> {
+        let (id, search) = search_term(filter.search.as_deref());
+        let unit = filter.unit.as_ref().map(ToString::to_string);
+        let state = filter.state.as_ref().map(ToString::to_string);
+        let limit = filter.limit.unwrap_or(DEFAULT_SEARCH_LIMIT) as i64;
+        let offset = filter.offset.unwrap_or_default() as i64;
+        let quotes = sqlx::query!(
+            r#"SELECT id as "id: Uuid", payment_request, expiry, state, amount, fee_reserve, unit, payment_preimage, fee_paid FROM bolt11_melt_quotes WHERE (?1 IS NULL OR id = ?1) AND (?2 IS NULL OR payment_request LIKE '%' || ?2 || '%') AND (?3 IS NULL OR unit = ?3) AND (?4 IS NULL OR state = ?4) ORDER BY expiry DESC, id LIMIT ?5 OFFSET ?6"#,
+            id,
+            search,
+            unit,
+            state,
+            limit,
+            offset
+        )
+        .map(|row| Bolt11MeltQuote {
+            quote_id: row.id,
+            payment_request: row.payment_request,
+            expiry: row.expiry as u64,
+            state: MeltQuoteState::from_str(&row.state).expect("invalid state in melt quote"),
+            amount: row.amount as u64,
+            fee_reserve: row.fee_reserve as u64,
+            unit: CurrencyUnit::from(row.unit),
+            payment_preimage: row.payment_preimage,
+            fee_paid: row.fee_paid.map(|fee_paid| fee_paid as u64),
+        })
+        .fetch_all(&mut **tx)
+        .await?;
+        Ok(quotes)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn update_bolt11_melt_quote_expiry(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+        expiry: u64,
+    ) -> Result<bool, MokshaMintError> {
+        let expiry = expiry as i64;
+        let unpaid = MeltQuoteState::Unpaid.to_string();
+        let result = sqlx::query!(
+            "UPDATE bolt11_melt_quotes SET expiry = ? WHERE id = ? AND state = ?",
+            expiry,
+            key,
+            unpaid
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn transition_bolt11_melt_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+        from: &MeltQuoteState,
+        to: &MeltQuoteState,
+    ) -> Result<bool, MokshaMintError> {
+        let to = to.to_string();
+        let from = from.to_string();
+        let result = sqlx::query!(
+            "UPDATE bolt11_melt_quotes SET state = ? WHERE id = ? AND state = ?",
+            to,
+            key,
+            from
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn set_bolt11_melt_quote_payment_id(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+        payment_id: &str,
+    ) -> Result<(), MokshaMintError> {
+        sqlx::query!(
+            "UPDATE bolt11_melt_quotes SET payment_id = ? WHERE id = ?",
+            payment_id,
+            key
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_bolt11_melt_quote_payment_id(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+    ) -> Result<Option<String>, MokshaMintError> {
+        let payment_id = sqlx::query!(
+            "SELECT payment_id FROM bolt11_melt_quotes WHERE id = ?",
+            key
+        )
+        .map(|row| row.payment_id)
+        .fetch_one(&mut **tx)
+        .await?;
+        Ok(payment_id)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn delete_expired_bolt11_melt_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        expired_before: u64,
+    ) -> Result<u64, MokshaMintError> {
+        let state = MeltQuoteState::Unpaid.to_string();
+        let expired_before = expired_before as i64;
+        let result = sqlx::query!(
+            "DELETE FROM bolt11_melt_quotes WHERE state = ? AND expiry < ? AND NOT EXISTS (SELECT 1 FROM pending_proofs WHERE pending_proofs.quote_id = bolt11_melt_quotes.id)",
+            state,
+            expired_before
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn delete_bolt11_melt_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        quote: &Bolt11MeltQuote,
+    ) -> Result<(), MokshaMintError> {
+        sqlx::query!(
+            "DELETE FROM bolt11_melt_quotes WHERE id = ?",
+            quote.quote_id
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_onchain_mint_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+    ) -> Result<BtcOnchainMintQuote, MokshaMintError> {
+        let quote: BtcOnchainMintQuote = sqlx::query!(
+            r#"SELECT id as "id: Uuid", address, amount, expiry, state FROM onchain_mint_quotes WHERE id = ?"#,
+            key
+        )
+        .map(|row| BtcOnchainMintQuote {
+            quote_id: row.id,
+            address: row.address,
+            expiry: row.expiry as u64,
+            state: MintBtcOnchainState::from_str(&row.state).expect("invalid state in mint quote"),
+            amount: row.amount as u64,
+            unit: CurrencyUnit::Sat,
+        })
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(quote)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_expired_onchain_mint_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        expired_before: u64,
+    ) -> Result<Vec<BtcOnchainMintQuote>, MokshaMintError> {
+        let state = MintBtcOnchainState::Unpaid.to_string();
+        let expired_before = expired_before as i64;
+        let quotes = sqlx::query!(
+            r#"SELECT id as "id: Uuid", address, amount, expiry, state FROM onchain_mint_quotes WHERE state = ? AND expiry < ?"#,
+            state,
+            expired_before
+        )
+        .map(|row| BtcOnchainMintQuote {
+            quote_id: row.id,
+            address: row.address,
+            expiry: row.expiry as u64,
+            state: MintBtcOnchainState::from_str(&row.state).expect("invalid state in mint quote"),
+            amount: row.amount as u64,
+            unit: CurrencyUnit::Sat,
+        })
+        .fetch_all(&mut **tx)
+        .await?;
+        Ok(quotes)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn add_onchain_mint_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        quote: &BtcOnchainMintQuote,
+    ) -> Result<(), MokshaMintError> {
+        let amount = quote.amount as i64;
+        let expiry = quote.expiry as i64;
+        let state = quote.state.to_string();
+        sqlx::query!(
+            "INSERT INTO onchain_mint_quotes (id, address, amount, expiry, state) VALUES (?, ?, ?, ?, ?)",
+            quote.quote_id,
+            quote.address,
+            amount,
+            expiry,
+            state,
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn update_onchain_mint_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        quote: &BtcOnchainMintQuote,
+    ) -> Result<(), MokshaMintError> {
+        let state = quote.state.to_string();
+        sqlx::query!(
+            "UPDATE onchain_mint_quotes SET state = ? WHERE id = ?",
+            state,
+            quote.quote_id
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn delete_onchain_mint_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        quote: &BtcOnchainMintQuote,
+    ) -> Result<(), MokshaMintError> {
+        sqlx::query!(
+            "DELETE FROM onchain_mint_quotes WHERE id = ?",
+            quote.quote_id
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn get_onchain_melt_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        key: &Uuid,
+    ) -> Result<BtcOnchainMeltQuote, MokshaMintError> {
+        let quote: BtcOnchainMeltQuote = sqlx::query!(
+            r#"SELECT id as "id: Uuid", amount, address, fee_total, fee_sat_per_vbyte, expiry, state, description FROM onchain_melt_quotes WHERE id = ?"#,
+            key
+        )
+        .map(|row| BtcOnchainMeltQuote {
+            quote_id: row.id,
+            address: row.address,
+            amount: row.amount as u64,
+            fee_total: row.fee_total as u64,
+            fee_sat_per_vbyte: row.fee_sat_per_vbyte as u32,
+            expiry: row.expiry as u64,
+            state: MeltBtcOnchainState::from_str(&row.state).expect("invalid state in melt quote"),
+            description: row.description
+        })
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(quote)
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn add_onchain_melt_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        quote: &BtcOnchainMeltQuote,
+    ) -> Result<(), MokshaMintError> {
+        let amount = quote.amount as i64;
+        let fee_total = quote.fee_total as i64;
+        let fee_sat_per_vbyte = quote.fee_sat_per_vbyte as i64;
+        let expiry = quote.expiry as i64;
+        let state = quote.state.to_string();
+        sqlx::query!(
+            "INSERT INTO onchain_melt_quotes (id, amount, address, fee_total, fee_sat_per_vbyte, expiry, state, description) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            quote.quote_id,
+            amount,
+            quote.address,
+            fee_total,
+            fee_sat_per_vbyte,
+            expiry,
+            state,
+            quote.description
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn update_onchain_melt_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        quote: &BtcOnchainMeltQuote,
+    ) -> Result<(), MokshaMintError> {
+        let state = quote.state.to_string();
+        sqlx::query!(
+            "UPDATE onchain_melt_quotes SET state = ? WHERE id = ?",
+            state,
+            quote.quote_id
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn delete_expired_onchain_melt_quotes(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        expired_before: u64,
+    ) -> Result<u64, MokshaMintError> {
+        let state = MeltBtcOnchainState::Unpaid.to_string();
+        let expired_before = expired_before as i64;
+        let result = sqlx::query!(
+            "DELETE FROM onchain_melt_quotes WHERE state = ? AND expiry < ? AND NOT EXISTS (SELECT 1 FROM pending_proofs WHERE pending_proofs.quote_id = onchain_melt_quotes.id)",
+            state,
+            expired_before
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    #[instrument(level = "debug", skip(self), err)]
+    async fn delete_onchain_melt_quote(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        quote: &BtcOnchainMeltQuote,
+    ) -> Result<(), MokshaMintError> {
+        sqlx::query!(
+            "DELETE FROM onchain_melt_quotes WHERE id = ?",
+            quote.quote_id
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+}
@@ -47,12 +47,18 @@ pub enum MokshaMintError {
     #[error("Lightning invoice not paid yet.")]
     InvoiceNotPaidYet,
 
+    #[error("Lightning invoice is already paid {0}")]
+    InvoiceAlreadyPaid(String),
+
     #[error("BTC-Onchain not paid yet.")]
     BtcOnchainNotPaidYet,
 
     #[error("Proof already used {0}")]
     ProofAlreadyUsed(String),
 
+    #[error("Proof is pending {0}")]
+    ProofPending(String),
+
     #[error("{0}")]
     SwapAmountMismatch(String),
 
@@ -68,15 +74,36 @@ pub enum MokshaMintError {
     #[error("Invalid quote {0}")]
     InvalidQuote(String),
 
+    #[error("Quote {0} is pending")]
+    QuotePending(String),
+
+    #[error("Quote {0} is already paid")]
+    QuoteAlreadyPaid(String),
+
+    #[error("Quote {0} has expired")]
+    QuoteExpired(String),
+
     #[error("Invalid quote uuid {0}")]
     InvalidUuid(#[from] uuid::Error),
 
+    #[error("Invalid subscription: {0}")]
+    InvalidSubscription(String),
+
     #[error("Keyset not found {0}")]
     KeysetNotFound(String),
 
+    #[error("Keyset {0} is inactive")]
+    InactiveKeyset(String),
+
     #[error("Currency not supported {0}")]
     CurrencyNotSupported(CurrencyUnit),
 
+    #[error("Unit mismatch: expected {0} but got {1}")]
+    UnitMismatch(CurrencyUnit, CurrencyUnit),
+
+    #[error("Price oracle error {0}")]
+    PriceOracle(String),
+
     #[error("Not Enough tokens. Required amount {0}")]
     NotEnoughTokens(u64),
 
@@ -86,6 +113,24 @@ pub enum MokshaMintError {
     #[error("PrivateKey in keyset not found")]
     PrivateKeyNotFound,
 
+    #[error("Amount {0} is not supported by keyset {1}")]
+    InvalidProofAmount(u64, String),
+
+    #[error("Invalid signature for proof {0}")]
+    InvalidProofSignature(String),
+
+    #[error("Invalid configuration, missing {}", .0.join(", "))]
+    Config(Vec<String>),
+
+    #[error("Unauthorized")]
+    Unauthorized,
+
+    #[error("Minting is disabled")]
+    MintingDisabled,
+
+    #[error("Melting is disabled")]
+    MeltingDisabled,
+
     #[error("MokshaCoreError: {0}")]
     MokshaCore(#[from] moksha_core::error::MokshaCoreError),
 }
@@ -99,6 +144,10 @@ impl IntoResponse for MokshaMintError {
             "detail": self.to_string(),
         }));
 
-        (StatusCode::BAD_REQUEST, body).into_response()
+        let status = match self {
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        (status, body).into_response()
     }
 }
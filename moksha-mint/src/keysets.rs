@@ -0,0 +1,120 @@
+//! This module defines the in-memory keyset registry of the mint.
+//!
+//! Every keyset is derived from the private key of the mint and the derivation path that is stored in the database.
+//! Only active keysets are used to sign new outputs, inactive keysets are kept to verify proofs that were issued before a rotation.
+
+use moksha_core::{keyset::MintKeyset, primitives::CurrencyUnit};
+
+use crate::model::KeysetInfo;
+
+#[derive(Debug, Clone)]
+pub struct MintKeysetEntry {
+    pub info: KeysetInfo,
+    pub keyset: MintKeyset,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MintKeysets(Vec<MintKeysetEntry>);
+
+impl MintKeysets {
+    pub fn new(private_key: &str, infos: Vec<KeysetInfo>) -> Self {
+        Self(
+            infos
+                .into_iter()
+                .map(|info| MintKeysetEntry {
+                    keyset: MintKeyset::new(private_key, &info.derivation_path),
+                    info,
+                })
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, id: &str) -> Option<&MintKeysetEntry> {
+        self.0.iter().find(|entry| entry.info.id == id)
+    }
+
+    pub fn active(&self, unit: &CurrencyUnit) -> Option<&MintKeysetEntry> {
+        self.0
+            .iter()
+            .rev()
+            .find(|entry| entry.info.active && entry.info.unit == *unit)
+    }
+
+    pub fn all(&self) -> &[MintKeysetEntry] {
+        &self.0
+    }
+
+    /// Returns a derivation path that is not used by any keyset yet by incrementing the last index of the latest derivation path
+    pub fn next_derivation_path(&self) -> String {
+        let latest = self
+            .0
+            .last()
+            .map(|entry| entry.info.derivation_path.clone())
+            .unwrap_or_default();
+        let (prefix, index) = match latest.rsplit_once('/') {
+            Some((prefix, last)) => (format!("{prefix}/"), last.parse::<u64>().ok()),
+            None => (String::new(), latest.parse::<u64>().ok()),
+        };
+        let (prefix, mut index) = match index {
+            Some(index) => (prefix, index + 1),
+            None if latest.is_empty() => (String::new(), 1),
+            None => (format!("{latest}/"), 1),
+        };
+        while self
+            .0
+            .iter()
+            .any(|entry| entry.info.derivation_path == format!("{prefix}{index}"))
+        {
+            index += 1;
+        }
+        format!("{prefix}{index}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moksha_core::primitives::CurrencyUnit;
+    use pretty_assertions::assert_eq;
+
+    use crate::{keysets::MintKeysets, model::KeysetInfo};
+
+    fn keyset_info(derivation_path: &str, active: bool) -> KeysetInfo {
+        KeysetInfo {
+            id: derivation_path.to_owned(),
+            unit: CurrencyUnit::Sat,
+            derivation_path: derivation_path.to_owned(),
+            active,
+            input_fee_ppk: 0,
+        }
+    }
+
+    #[test]
+    fn test_next_derivation_path() {
+        let next = |paths: &[&str]| {
+            MintKeysets::new(
+                "TEST_PRIVATE_KEY",
+                paths.iter().map(|path| keyset_info(path, true)).collect(),
+            )
+            .next_derivation_path()
+        };
+        assert_eq!("1", next(&[]));
+        assert_eq!("1", next(&[""]));
+        assert_eq!("0/0/0/1", next(&["0/0/0/0"]));
+        assert_eq!("m/0'/1", next(&["m/0'"]));
+        assert_eq!("0/4", next(&["0/3", "0/2"]));
+    }
+
+    #[test]
+    fn test_active_keyset() {
+        let keysets = MintKeysets::new(
+            "TEST_PRIVATE_KEY",
+            vec![keyset_info("0/0", false), keyset_info("0/1", true)],
+        );
+        let active = keysets
+            .active(&CurrencyUnit::Sat)
+            .expect("no active keyset");
+        assert_eq!("0/1", active.info.derivation_path);
+        assert!(keysets.active(&CurrencyUnit::Usd).is_none());
+        assert!(keysets.get("0/0").is_some());
+    }
+}
@@ -11,7 +11,10 @@ use crate::{
     model::{CreateInvoiceParams, CreateInvoiceResult, PayInvoiceResult},
 };
 
-use super::{error::LightningError, Lightning};
+use super::{
+    error::LightningError, percent_fee_reserve, verify_backend_fee_reserve, Lightning,
+    PaymentStatus,
+};
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default, Parser)]
 pub struct AlbyLightningSettings {
@@ -45,6 +48,11 @@ impl AlbyLightning {
         }
     }
 }
+/// alby holds back 1% but at least 10 sat for the fees of a payment
+pub(crate) fn backend_fee_reserve(amount_msat: u64) -> u64 {
+    percent_fee_reserve(amount_msat, 1, 10_000)
+}
+
 #[async_trait]
 impl Lightning for AlbyLightning {
     async fn is_invoice_paid(&self, invoice: String) -> Result<bool, MokshaMintError> {
@@ -55,6 +63,17 @@ impl Lightning for AlbyLightning {
             .await?)
     }
 
+    async fn get_payment_status(
+        &self,
+        payment_hash: &str,
+    ) -> Result<PaymentStatus, MokshaMintError> {
+        match self.client.get_payment_status(payment_hash).await {
+            // alby doesn't store failed payments, but a payment that isn't found may also not have been sent yet
+            Err(LightningError::NotFound) => Ok(PaymentStatus::Unknown),
+            result => Ok(result?),
+        }
+    }
+
     async fn create_invoice(&self, amount: u64) -> Result<CreateInvoiceResult, MokshaMintError> {
         Ok(self
             .client
@@ -72,12 +91,20 @@ impl Lightning for AlbyLightning {
     async fn pay_invoice(
         &self,
         payment_request: String,
+        max_fee_msat: u64,
+        _payment_id: Option<String>,
     ) -> Result<PayInvoiceResult, MokshaMintError> {
+        // the api of alby has no fee limit
+        verify_backend_fee_reserve(&payment_request, max_fee_msat, backend_fee_reserve)?;
         self.client
             .pay_invoice(&payment_request)
             .await
             .map_err(|err| MokshaMintError::PayInvoice(payment_request, err))
     }
+
+    async fn get_balance(&self) -> Result<Option<u64>, MokshaMintError> {
+        Ok(Some(self.client.get_balance().await?.saturating_mul(1_000)))
+    }
 }
 
 #[derive(Clone)]
@@ -190,10 +217,37 @@ impl AlbyClient {
                 .as_str()
                 .expect("payment_hash is empty")
                 .to_owned(),
+            payment_preimage: response["payment_preimage"].as_str().map(ToOwned::to_owned),
             total_fees: 0, // FIXME alby does not return fees at the moment
         })
     }
 
+    pub async fn get_payment_status(
+        &self,
+        payment_hash: &str,
+    ) -> Result<PaymentStatus, LightningError> {
+        // outgoing payments are returned by the invoices endpoint as well
+        let body = self.make_get(&format!("invoices/{payment_hash}")).await?;
+        let response = serde_json::from_str::<serde_json::Value>(&body)?;
+
+        Ok(match response["settled"].as_bool() {
+            Some(true) => PaymentStatus::Paid(PayInvoiceResult {
+                payment_hash: payment_hash.to_owned(),
+                payment_preimage: response["preimage"].as_str().map(ToOwned::to_owned),
+                total_fees: response["fee"].as_u64().unwrap_or_default(),
+            }),
+            _ => PaymentStatus::Pending,
+        })
+    }
+
+    /// returns the balance of the account in sat
+    pub async fn get_balance(&self) -> Result<u64, LightningError> {
+        let body = self.make_get("balance").await?;
+        Ok(serde_json::from_str::<serde_json::Value>(&body)?["balance"]
+            .as_u64()
+            .unwrap_or_default())
+    }
+
     pub async fn is_invoice_paid(&self, payment_hash: &str) -> Result<bool, LightningError> {
         let body = self.make_get(&format!("invoices/{payment_hash}")).await?;
         Ok(serde_json::from_str::<serde_json::Value>(&body)?["settled"]
@@ -1,7 +1,11 @@
 use async_trait::async_trait;
 use clap::Parser;
 use cln_grpc::pb::{amount_or_any, Amount, AmountOrAny};
-use cln_grpc::pb::{listinvoices_invoices::ListinvoicesInvoicesStatus, node_client::NodeClient};
+use cln_grpc::pb::{
+    listinvoices_invoices::ListinvoicesInvoicesStatus, listpays_pays::ListpaysPaysStatus,
+    node_client::NodeClient, waitanyinvoice_response::WaitanyinvoiceStatus,
+};
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::fmt::{self};
 use std::{fmt::Formatter, path::PathBuf, sync::Arc};
@@ -12,11 +16,12 @@ use crate::{
 };
 use tonic::transport::{Certificate, ClientTlsConfig, Identity};
 
-use super::Lightning;
+use super::{error::LightningError, InvoiceStream, Lightning, PaymentStatus};
 
 use secp256k1::rand;
 use std::fs::read;
 use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
+use tracing::warn;
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default, Parser)]
 pub struct ClnLightningSettings {
@@ -78,6 +83,26 @@ impl ClnLightning {
     }
 }
 
+/// Maps the error codes of the cln `pay` command that mean the payment definitely failed. cln-grpc only passes
+/// them on in the message of the status, other errors, e.g. a lost connection, leave the payment outcome unknown.
+fn cln_pay_error(status: &tonic::Status, max_fee_msat: u64) -> Option<LightningError> {
+    let code = status
+        .message()
+        .split_once("code: Some(")
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .and_then(|(code, _)| code.parse::<i32>().ok())?;
+    match code {
+        // no route, or no route within the fee limit; cln doesn't report the fee of the cheapest route
+        205 | 206 => Some(LightningError::FeeLimitExceeded(
+            max_fee_msat.saturating_add(1),
+            max_fee_msat,
+        )),
+        // rejected by the destination, invoice expired or no attempt left
+        203 | 207 | 210 => Some(LightningError::PaymentFailed),
+        _ => None,
+    }
+}
+
 #[async_trait]
 impl Lightning for ClnLightning {
     async fn is_invoice_paid(&self, payment_request: String) -> Result<bool, MokshaMintError> {
@@ -106,6 +131,53 @@ impl Lightning for ClnLightning {
         Ok(invoice.status() == ListinvoicesInvoicesStatus::Paid)
     }
 
+    async fn get_payment_status(
+        &self,
+        payment_hash: &str,
+    ) -> Result<PaymentStatus, MokshaMintError> {
+        let pays = self
+            .client_lock()
+            .await
+            .expect("failed to lock client")
+            .list_pays(cln_grpc::pb::ListpaysRequest {
+                bolt11: None,
+                payment_hash: Some(
+                    hex::decode(payment_hash)
+                        .map_err(|_| MokshaMintError::InvoiceNotFound(payment_hash.to_owned()))?,
+                ),
+                status: None,
+            })
+            .await
+            .map_err(|err| MokshaMintError::ClnConnectError(err.into()))?
+            .into_inner()
+            .pays;
+
+        // a payment can have several attempts, it is paid if one of them completed
+        if let Some(pay) = pays
+            .iter()
+            .find(|pay| pay.status() == ListpaysPaysStatus::Complete)
+        {
+            let amount = pay.amount_msat.as_ref().map_or(0, |amount| amount.msat);
+            let amount_sent = pay
+                .amount_sent_msat
+                .as_ref()
+                .map_or(0, |amount| amount.msat);
+            return Ok(PaymentStatus::Paid(PayInvoiceResult {
+                payment_hash: payment_hash.to_owned(),
+                payment_preimage: pay.preimage.as_ref().map(hex::encode),
+                total_fees: amount_sent.saturating_sub(amount) / 1_000,
+            }));
+        }
+        if pays
+            .iter()
+            .any(|pay| pay.status() == ListpaysPaysStatus::Pending)
+        {
+            return Ok(PaymentStatus::Pending);
+        }
+        // no attempt at all means the payment was never sent
+        Ok(PaymentStatus::Failed)
+    }
+
     async fn create_invoice(&self, amount: u64) -> Result<CreateInvoiceResult, MokshaMintError> {
         let amount_msat = Some(AmountOrAny {
             value: Some(amount_or_any::Value::Amount(Amount {
@@ -136,16 +208,59 @@ impl Lightning for ClnLightning {
         })
     }
 
+    async fn subscribe_invoices(&self) -> Result<Option<InvoiceStream>, MokshaMintError> {
+        // waitanyinvoice blocks until the next invoice is paid, so the subscription uses its own client
+        let client = self
+            .client_lock()
+            .await
+            .map_err(MokshaMintError::ClnConnectError)?
+            .clone();
+        let invoices = futures_util::stream::unfold(
+            (client, None),
+            |(mut client, lastpay_index)| async move {
+                let response = client
+                    .wait_any_invoice(cln_grpc::pb::WaitanyinvoiceRequest {
+                        lastpay_index,
+                        timeout: None,
+                    })
+                    .await;
+                Some(match response {
+                    Ok(response) => {
+                        let invoice = response.into_inner();
+                        let lastpay_index = invoice.pay_index.or(lastpay_index);
+                        (Ok(invoice), (client, lastpay_index))
+                    }
+                    Err(status) => (
+                        Err(MokshaMintError::ClnConnectError(status.into())),
+                        (client, lastpay_index),
+                    ),
+                })
+            },
+        )
+        .filter_map(|invoice| async move {
+            match invoice {
+                Ok(invoice) if invoice.status() == WaitanyinvoiceStatus::Paid => {
+                    Some(Ok(hex::encode(invoice.payment_hash)))
+                }
+                Ok(_) => None,
+                Err(err) => Some(Err(err)),
+            }
+        });
+        Ok(Some(Box::pin(invoices)))
+    }
+
     async fn pay_invoice(
         &self,
         payment_request: String,
+        max_fee_msat: u64,
+        _payment_id: Option<String>,
     ) -> Result<PayInvoiceResult, MokshaMintError> {
         let payment = self
             .client_lock()
             .await
             .expect("failed to lock client") //FIXME map error
             .pay(cln_grpc::pb::PayRequest {
-                bolt11: payment_request,
+                bolt11: payment_request.clone(),
                 amount_msat: None,
                 label: None,
                 riskfactor: None,
@@ -155,18 +270,72 @@ impl Lightning for ClnLightning {
                 exemptfee: None,
                 localinvreqid: None,
                 exclude: vec![],
-                maxfee: None,
+                // cln doesn't try routes with a higher fee and fails the payment if there is none within the limit
+                maxfee: Some(Amount { msat: max_fee_msat }),
                 description: None,
             })
             .await
-            .expect("failed to pay invoice")
+            .map_err(|err| {
+                warn!("cln payment failed: {}", err);
+                match cln_pay_error(&err, max_fee_msat) {
+                    Some(lightning_err) => {
+                        MokshaMintError::PayInvoice(payment_request, lightning_err)
+                    }
+                    None => MokshaMintError::ClnConnectError(err.into()),
+                }
+            })?
             .into_inner();
 
+        let fees_msat = payment
+            .amount_sent_msat
+            .zip(payment.amount_msat)
+            .map_or(0, |(sent, amount)| sent.msat.saturating_sub(amount.msat));
         Ok(PayInvoiceResult {
             payment_hash: hex::encode(payment.payment_hash),
-            total_fees: payment.amount_sent_msat.unwrap().msat - payment.amount_msat.unwrap().msat, // FIXME check if this is correct
+            payment_preimage: Some(hex::encode(payment.payment_preimage)),
+            total_fees: fees_msat.div_ceil(1_000),
         })
     }
+
+    async fn get_balance(&self) -> Result<Option<u64>, MokshaMintError> {
+        let funds = self
+            .client_lock()
+            .await
+            .expect("failed to lock client")
+            .list_funds(cln_grpc::pb::ListfundsRequest { spent: None })
+            .await
+            .map_err(|err| MokshaMintError::ClnConnectError(err.into()))?
+            .into_inner();
+        Ok(Some(
+            funds
+                .channels
+                .iter()
+                .filter_map(|channel| channel.our_amount_msat.as_ref())
+                .map(|amount| amount.msat)
+                .sum(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cln_pay_error;
+    use crate::lightning::error::LightningError;
+
+    #[test]
+    fn test_cln_pay_error() {
+        let status = |message: &str| tonic::Status::new(tonic::Code::Unknown, message);
+        assert!(matches!(
+            cln_pay_error(&status("Error calling method Pay: RpcError { code: Some(206), message: \"Route wanted fee of 5000msat\", data: None }"), 4_000),
+            Some(LightningError::FeeLimitExceeded(4_001, 4_000))
+        ));
+        assert!(matches!(
+            cln_pay_error(&status("Error calling method Pay: RpcError { code: Some(207), message: \"Invoice expired\", data: None }"), 4_000),
+            Some(LightningError::PaymentFailed)
+        ));
+        assert!(cln_pay_error(&status("Error calling method Pay: RpcError { code: Some(200), message: \"pending\", data: None }"), 4_000).is_none());
+        assert!(cln_pay_error(&tonic::Status::unavailable("connection lost"), 4_000).is_none());
+    }
 }
 
 // mod tests {
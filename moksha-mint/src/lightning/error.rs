@@ -9,6 +9,9 @@ pub enum LightningError {
     #[error("serde error: {0}")]
     SerdeError(#[from] serde_json::Error),
 
+    #[error("websocket error: {0}")]
+    WebSocketError(#[from] tokio_tungstenite::tungstenite::Error),
+
     #[error("Not found")]
     NotFound,
 
@@ -17,4 +20,10 @@ pub enum LightningError {
 
     #[error("Payment failed")]
     PaymentFailed,
+
+    #[error("Payment is pending")]
+    PaymentPending,
+
+    #[error("Routing fee of {0} msat exceeds the maximum fee of {1} msat")]
+    FeeLimitExceeded(u64, u64),
 }
@@ -3,8 +3,10 @@ use std::fmt::{self, Formatter};
 use async_trait::async_trait;
 
 use clap::Parser;
+use futures_util::{Stream, StreamExt};
 use hyper::{header::CONTENT_TYPE, http::HeaderValue};
 use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::Message;
 use url::Url;
 
 use crate::{
@@ -12,7 +14,10 @@ use crate::{
     model::{CreateInvoiceParams, CreateInvoiceResult, PayInvoiceResult},
 };
 
-use super::{error::LightningError, Lightning};
+use super::{
+    error::LightningError, percent_fee_reserve, verify_backend_fee_reserve, InvoiceStream,
+    Lightning, PaymentStatus,
+};
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default, Parser)]
 pub struct LnbitsLightningSettings {
@@ -20,6 +25,9 @@ pub struct LnbitsLightningSettings {
     pub admin_key: Option<String>,
     #[clap(long, env = "MINT_LNBITS_URL")]
     pub url: Option<String>, // FIXME use Url type instead
+    /// the invoice key of the wallet. If it is set, settled invoices are received over the websocket of lnbits
+    #[clap(long, env = "MINT_LNBITS_INVOICE_KEY")]
+    pub invoice_key: Option<String>,
 }
 
 impl LnbitsLightningSettings {
@@ -27,6 +35,7 @@ impl LnbitsLightningSettings {
         Self {
             admin_key: Some(admin_key.to_owned()),
             url: Some(url.to_owned()),
+            invoice_key: None,
         }
     }
 }
@@ -45,6 +54,7 @@ impl fmt::Display for LnbitsLightningSettings {
 #[derive(Clone)]
 pub struct LnbitsLightning {
     pub client: LNBitsClient,
+    invoice_key: Option<String>,
 }
 
 impl LnbitsLightning {
@@ -52,8 +62,19 @@ impl LnbitsLightning {
         Self {
             client: LNBitsClient::new(&admin_key, &url, None)
                 .expect("Can not create Lnbits client"),
+            invoice_key: None,
         }
     }
+
+    pub fn with_invoice_key(mut self, invoice_key: Option<String>) -> Self {
+        self.invoice_key = invoice_key;
+        self
+    }
+}
+
+/// lnbits holds back 1% but at least 2 sat of the wallet balance for the fees of a payment by default
+pub(crate) fn backend_fee_reserve(amount_msat: u64) -> u64 {
+    percent_fee_reserve(amount_msat, 1, 2_000)
 }
 
 #[async_trait]
@@ -66,6 +87,29 @@ impl Lightning for LnbitsLightning {
             .await?)
     }
 
+    async fn subscribe_invoices(&self) -> Result<Option<InvoiceStream>, MokshaMintError> {
+        let Some(invoice_key) = &self.invoice_key else {
+            return Ok(None);
+        };
+        let payments = self
+            .client
+            .subscribe_payments(invoice_key)
+            .await?
+            .map(|payment| payment.map_err(MokshaMintError::from));
+        Ok(Some(Box::pin(payments)))
+    }
+
+    async fn get_payment_status(
+        &self,
+        payment_hash: &str,
+    ) -> Result<PaymentStatus, MokshaMintError> {
+        match self.client.get_payment_status(payment_hash).await {
+            // the payment never reached lnbits
+            Err(LightningError::NotFound) => Ok(PaymentStatus::Failed),
+            result => Ok(result?),
+        }
+    }
+
     async fn create_invoice(&self, amount: u64) -> Result<CreateInvoiceResult, MokshaMintError> {
         Ok(self
             .client
@@ -83,12 +127,20 @@ impl Lightning for LnbitsLightning {
     async fn pay_invoice(
         &self,
         payment_request: String,
+        max_fee_msat: u64,
+        _payment_id: Option<String>,
     ) -> Result<PayInvoiceResult, MokshaMintError> {
+        // the api of lnbits has no fee limit
+        verify_backend_fee_reserve(&payment_request, max_fee_msat, backend_fee_reserve)?;
         self.client
             .pay_invoice(&payment_request)
             .await
             .map_err(|err| MokshaMintError::PayInvoice(payment_request, err))
     }
+
+    async fn get_balance(&self) -> Result<Option<u64>, MokshaMintError> {
+        Ok(Some(self.client.get_balance().await?))
+    }
 }
 #[derive(Clone)]
 pub struct LNBitsClient {
@@ -166,6 +218,39 @@ impl LNBitsClient {
 }
 
 impl LNBitsClient {
+    /// Streams the payment hashes of the settled incoming payments of the wallet with the invoice key
+    pub async fn subscribe_payments(
+        &self,
+        invoice_key: &str,
+    ) -> Result<impl Stream<Item = Result<String, LightningError>>, LightningError> {
+        let mut url = self.lnbits_url.join(&format!("api/v1/ws/{invoice_key}"))?;
+        let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+        url.set_scheme(scheme)
+            .expect("http urls can be changed to websocket urls");
+        let (socket, _) = tokio_tungstenite::connect_async(url.as_str()).await?;
+
+        Ok(socket.filter_map(|message| async move {
+            let text = match message {
+                Ok(Message::Text(text)) => text,
+                Ok(_) => return None,
+                Err(err) => return Some(Err(err.into())),
+            };
+            let payment = serde_json::from_str::<serde_json::Value>(&text).ok()?["payment"].take();
+            // incoming payments have a positive amount, older versions of lnbits don't have a status
+            let settled = payment["status"]
+                .as_str()
+                .map_or(payment["pending"].as_bool() == Some(false), |status| {
+                    status == "success"
+                });
+            if payment["amount"].as_i64().unwrap_or_default() <= 0 || !settled {
+                return None;
+            }
+            payment["payment_hash"]
+                .as_str()
+                .map(|payment_hash| Ok(payment_hash.to_owned()))
+        }))
+    }
+
     pub async fn create_invoice(
         &self,
         params: &CreateInvoiceParams,
@@ -212,9 +297,19 @@ impl LNBitsClient {
             .as_str()
             .expect("payment_hash is empty")
             .to_owned();
+
+        // the preimage and the fee are not part of the payment response
+        let body = self
+            .make_get(&format!("api/v1/payments/{payment_hash}"))
+            .await?;
+        let response = serde_json::from_str::<serde_json::Value>(&body)?;
         Ok(PayInvoiceResult {
             payment_hash,
-            total_fees: 0,
+            payment_preimage: response["preimage"].as_str().map(ToOwned::to_owned),
+            // outgoing payments have a negative fee in msat
+            total_fees: response["details"]["fee"]
+                .as_i64()
+                .map_or(0, |fee| fee.unsigned_abs() / 1_000),
         })
     }
 
@@ -227,10 +322,48 @@ impl LNBitsClient {
             .as_bool()
             .unwrap_or(false))
     }
+
+    /// returns the balance of the wallet in msat
+    pub async fn get_balance(&self) -> Result<u64, LightningError> {
+        let body = self.make_get("api/v1/wallet").await?;
+        Ok(serde_json::from_str::<serde_json::Value>(&body)?["balance"]
+            .as_u64()
+            .unwrap_or_default())
+    }
+
+    pub async fn get_payment_status(
+        &self,
+        payment_hash: &str,
+    ) -> Result<PaymentStatus, LightningError> {
+        let body = self
+            .make_get(&format!("api/v1/payments/{payment_hash}"))
+            .await?;
+        let response = serde_json::from_str::<serde_json::Value>(&body)?;
+
+        Ok(
+            match (
+                response["paid"].as_bool(),
+                response["details"]["pending"].as_bool(),
+            ) {
+                (Some(true), _) => PaymentStatus::Paid(PayInvoiceResult {
+                    payment_hash: payment_hash.to_owned(),
+                    payment_preimage: response["preimage"].as_str().map(ToOwned::to_owned),
+                    // outgoing payments have a negative fee in msat
+                    total_fees: response["details"]["fee"]
+                        .as_i64()
+                        .map_or(0, |fee| fee.unsigned_abs() / 1_000),
+                }),
+                (_, Some(false)) => PaymentStatus::Failed,
+                _ => PaymentStatus::Pending,
+            },
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::error::MokshaMintError;
+    use crate::lightning::error::LightningError;
     use crate::lightning::lnbits::LnbitsLightning;
     use crate::lightning::Lightning;
 
@@ -262,4 +395,22 @@ mod tests {
         assert!(decoded_invoice.is_err());
         Ok(())
     }
+    #[tokio::test]
+    async fn test_pay_invoice_fee_reserve_above_limit() -> anyhow::Result<()> {
+        let invoice = "lnbcrt55550n1pjga687pp5ac8ja6n5hn90huztxxp746w48vtj8ys5uvze6749dvcsd5j5sdvsdqqcqzzsxqyz5vqsp5kzzq0ycxspxjygsxkfkexkkejjr5ggeyl56mwa7s0ygk2q8z92ns9qyyssqt7myq7sryffasx8v47al053ut4vqts32e9hvedvs7eml5h9vdrtj3k5m72yex5jv355jpuzk2xjjn5468cz87nhp50jyr2al2a5zjvgq2xs5uq".to_string();
+
+        let lightning =
+            LnbitsLightning::new("admin_key".to_string(), "http://localhost:5000".to_string());
+
+        // lnbits holds back 1% of 5555 sat, which is more than the limit, so the invoice is not paid
+        let result = lightning.pay_invoice(invoice, 50_000, None).await;
+        assert!(matches!(
+            result,
+            Err(MokshaMintError::PayInvoice(
+                _,
+                LightningError::FeeLimitExceeded(55_550, 50_000)
+            ))
+        ));
+        Ok(())
+    }
 }
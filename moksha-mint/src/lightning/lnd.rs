@@ -12,13 +12,20 @@ use crate::{
 };
 use async_trait::async_trait;
 use clap::Parser;
-use fedimint_tonic_lnd::Client;
+use fedimint_tonic_lnd::{
+    lnrpc::{
+        fee_limit::Limit, invoice::InvoiceState, payment::PaymentStatus as LndPaymentStatus,
+        FeeLimit,
+    },
+    Client,
+};
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, warn};
 use url::Url;
 
-use super::Lightning;
+use super::{error::LightningError, InvoiceStream, Lightning, PaymentStatus};
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default, Parser)]
 pub struct LndLightningSettings {
@@ -63,6 +70,9 @@ impl fmt::Display for LndLightningSettings {
     }
 }
 
+/// the payment error of `SendPaymentSync` if lnd finds no route, e.g. because every route exceeds the fee limit
+const LND_NO_ROUTE_ERROR: &str = "unable to find a path to destination";
+
 pub struct LndLightning(Arc<Mutex<Client>>);
 
 impl LndLightning {
@@ -85,6 +95,13 @@ impl LndLightning {
         let guard = self.0.lock().await;
         Ok(MutexGuard::map(guard, |client| client.lightning()))
     }
+
+    pub async fn router_lock(
+        &self,
+    ) -> Result<MappedMutexGuard<'_, fedimint_tonic_lnd::RouterClient>, MokshaMintError> {
+        let guard = self.0.lock().await;
+        Ok(MutexGuard::map(guard, |client| client.router()))
+    }
 }
 
 #[async_trait]
@@ -133,9 +150,14 @@ impl Lightning for LndLightning {
     async fn pay_invoice(
         &self,
         payment_request: String,
+        max_fee_msat: u64,
+        _payment_id: Option<String>,
     ) -> Result<PayInvoiceResult, MokshaMintError> {
         let pay_req = fedimint_tonic_lnd::lnrpc::SendRequest {
-            payment_request,
+            payment_request: payment_request.clone(),
+            fee_limit: Some(FeeLimit {
+                limit: Some(Limit::FixedMsat(max_fee_msat as i64)),
+            }),
             ..Default::default()
         };
         let payment_response = self
@@ -145,15 +167,107 @@ impl Lightning for LndLightning {
             .await?
             .into_inner();
 
+        // lnd reports failed payments in the response, without a route within the fee limit it reports no route
+        if !payment_response.payment_error.is_empty() {
+            warn!("lnd payment failed: {}", payment_response.payment_error);
+            let err = if payment_response.payment_error == LND_NO_ROUTE_ERROR {
+                // lnd doesn't report the fee of the cheapest route, only that there is none within the limit
+                LightningError::FeeLimitExceeded(max_fee_msat.saturating_add(1), max_fee_msat)
+            } else {
+                LightningError::PaymentFailed
+            };
+            return Err(MokshaMintError::PayInvoice(payment_request, err));
+        }
+
+        // fees are rounded up, so the paid fees are never reported below the fee reserve they are checked against
         let total_fees = payment_response
             .payment_route
-            .map_or(0, |route| route.total_fees_msat / 1_000) as u64;
+            .map_or(0, |route| (route.total_fees_msat as u64).div_ceil(1_000));
 
         debug!("lnd total_fees: {}", total_fees);
 
         Ok(PayInvoiceResult {
             payment_hash: hex::encode(payment_response.payment_hash),
+            payment_preimage: Some(hex::encode(payment_response.payment_preimage)),
             total_fees,
         })
     }
+
+    #[instrument(skip(self), err)]
+    async fn subscribe_invoices(&self) -> Result<Option<InvoiceStream>, MokshaMintError> {
+        let request = fedimint_tonic_lnd::lnrpc::InvoiceSubscription {
+            add_index: 0,
+            settle_index: 0,
+        };
+        let invoices = self
+            .client_lock()
+            .await?
+            .subscribe_invoices(fedimint_tonic_lnd::tonic::Request::new(request))
+            .await?
+            .into_inner();
+
+        // lnd sends an update for every added invoice as well, only the settled ones are passed on
+        Ok(Some(Box::pin(invoices.filter_map(|invoice| async move {
+            match invoice {
+                Ok(invoice) if invoice.state() == InvoiceState::Settled => {
+                    Some(Ok(hex::encode(invoice.r_hash)))
+                }
+                Ok(_) => None,
+                Err(status) => Some(Err(status.into())),
+            }
+        }))))
+    }
+
+    #[instrument(skip(self), err)]
+    async fn get_balance(&self) -> Result<Option<u64>, MokshaMintError> {
+        let response = self
+            .client_lock()
+            .await?
+            .channel_balance(fedimint_tonic_lnd::tonic::Request::new(
+                fedimint_tonic_lnd::lnrpc::ChannelBalanceRequest {},
+            ))
+            .await?
+            .into_inner();
+        Ok(response.local_balance.map(|balance| balance.msat))
+    }
+
+    #[instrument(skip(self), err)]
+    async fn get_payment_status(
+        &self,
+        payment_hash: &str,
+    ) -> Result<PaymentStatus, MokshaMintError> {
+        let request = fedimint_tonic_lnd::routerrpc::TrackPaymentRequest {
+            payment_hash: hex::decode(payment_hash)
+                .map_err(|_| MokshaMintError::InvoiceNotFound(payment_hash.to_owned()))?,
+            no_inflight_updates: false,
+        };
+        let response = self
+            .router_lock()
+            .await?
+            .track_payment_v2(fedimint_tonic_lnd::tonic::Request::new(request))
+            .await;
+        let mut updates = match response {
+            Ok(response) => response.into_inner(),
+            // lnd doesn't know the payment, so it was never sent
+            Err(status) if status.code() == fedimint_tonic_lnd::tonic::Code::NotFound => {
+                return Ok(PaymentStatus::Failed)
+            }
+            Err(status) => return Err(status.into()),
+        };
+
+        // the first update contains the current state of the payment
+        let Some(payment) = updates.message().await? else {
+            return Ok(PaymentStatus::Unknown);
+        };
+        Ok(match payment.status() {
+            LndPaymentStatus::Succeeded => PaymentStatus::Paid(PayInvoiceResult {
+                payment_hash: payment.payment_hash,
+                payment_preimage: Some(payment.payment_preimage),
+                total_fees: (payment.fee_msat as u64).div_ceil(1_000),
+            }),
+            LndPaymentStatus::Failed => PaymentStatus::Failed,
+            LndPaymentStatus::InFlight => PaymentStatus::Pending,
+            LndPaymentStatus::Unknown => PaymentStatus::Unknown,
+        })
+    }
 }
@@ -3,9 +3,11 @@ use crate::{
     model::{CreateInvoiceResult, PayInvoiceResult},
 };
 use async_trait::async_trait;
+use futures_util::Stream;
 use lightning_invoice::Bolt11Invoice as LNInvoice;
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Formatter};
+use std::pin::Pin;
 
 pub mod alby;
 pub mod cln;
@@ -18,6 +20,7 @@ pub mod strike;
 use mockall::automock;
 use std::str::FromStr;
 
+use self::error::LightningError;
 use self::lnd::LndLightningSettings;
 use self::{
     alby::AlbyLightningSettings, cln::ClnLightningSettings, lnbits::LnbitsLightningSettings,
@@ -46,18 +49,104 @@ impl fmt::Display for LightningType {
     }
 }
 
+/// The state of an outgoing payment as reported by the lightning backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaymentStatus {
+    /// the payment succeeded, the preimage and fees are set if the backend reports them
+    Paid(PayInvoiceResult),
+    Failed,
+    Pending,
+    /// the backend can not look up outgoing payments
+    Unknown,
+}
+
+/// Settled incoming invoices, identified by their hex encoded payment hash
+pub type InvoiceStream = Pin<Box<dyn Stream<Item = Result<String, MokshaMintError>> + Send>>;
+
 #[cfg_attr(test, automock)]
 #[async_trait]
 pub trait Lightning: Send + Sync {
     async fn is_invoice_paid(&self, invoice: String) -> Result<bool, MokshaMintError>;
     async fn create_invoice(&self, amount: u64) -> Result<CreateInvoiceResult, MokshaMintError>;
+    /// Pays the invoice without paying more than `max_fee_msat` in routing fees. Fails with
+    /// `LightningError::FeeLimitExceeded` if no payment within the limit is possible. Backends whose api has no fee
+    /// limit (lnbits and alby) only pay invoices for which the fee reserve they hold back is within the limit.
+    /// `payment_id` is the id returned by `create_payment`, if any.
     async fn pay_invoice(
         &self,
         payment_request: String,
+        max_fee_msat: u64,
+        payment_id: Option<String>,
     ) -> Result<PayInvoiceResult, MokshaMintError>;
 
+    /// Creates the payment of the invoice at backends that don't identify outgoing payments by their payment hash
+    /// (strike) and returns its id. The mint stores the id on the melt quote before the invoice is paid, so the
+    /// payment can be looked up after a restart. Other backends return None.
+    async fn create_payment(
+        &self,
+        _payment_request: &str,
+        _max_fee_msat: u64,
+    ) -> Result<Option<String>, MokshaMintError> {
+        Ok(None)
+    }
+
+    /// Returns the state of the outgoing payment with the given id, the hex encoded payment hash unless
+    /// `create_payment` returned another id
+    async fn get_payment_status(&self, payment_id: &str) -> Result<PaymentStatus, MokshaMintError>;
+
+    /// Subscribes to the settlements of incoming invoices. Returns None if the backend can't stream settlements,
+    /// the mint polls `is_invoice_paid` instead.
+    async fn subscribe_invoices(&self) -> Result<Option<InvoiceStream>, MokshaMintError> {
+        Ok(None)
+    }
+
+    /// Returns the balance of the lightning channels that can be spent by the mint in msat. Returns None if the
+    /// backend can't report its balance.
+    async fn get_balance(&self) -> Result<Option<u64>, MokshaMintError> {
+        Ok(None)
+    }
+
     async fn decode_invoice(&self, payment_request: String) -> Result<LNInvoice, MokshaMintError> {
         LNInvoice::from_str(&payment_request)
             .map_err(|err| MokshaMintError::DecodeInvoice(payment_request, err))
     }
 }
+
+impl LightningType {
+    /// Returns the fee reserve in msat that a backend without a fee limit holds back for paying `amount_msat`. The
+    /// backend refuses payments with a lower fee limit, so melt quotes must not reserve less.
+    pub fn backend_fee_reserve(&self, amount_msat: u64) -> Option<u64> {
+        match self {
+            Self::Lnbits(_) => Some(lnbits::backend_fee_reserve(amount_msat)),
+            Self::Alby(_) => Some(alby::backend_fee_reserve(amount_msat)),
+            Self::Strike(_) | Self::Lnd(_) | Self::Cln(_) => None,
+        }
+    }
+}
+
+/// Returns `fee_percent` of the amount rounded up, but at least `fee_min_msat`
+pub(crate) fn percent_fee_reserve(amount_msat: u64, fee_percent: u64, fee_min_msat: u64) -> u64 {
+    std::cmp::max((amount_msat * fee_percent).div_ceil(100), fee_min_msat)
+}
+
+/// Checks the fee limit for backends that can't limit the routing fees of a payment. The fees of these backends are
+/// capped by the fee reserve they hold back for a payment, so the payment is refused if the reserve exceeds
+/// `max_fee_msat`.
+pub(crate) fn verify_backend_fee_reserve(
+    payment_request: &str,
+    max_fee_msat: u64,
+    backend_fee_reserve: fn(u64) -> u64,
+) -> Result<(), MokshaMintError> {
+    let amount_msat = LNInvoice::from_str(payment_request)
+        .map_err(|err| MokshaMintError::DecodeInvoice(payment_request.to_owned(), err))?
+        .amount_milli_satoshis()
+        .unwrap_or_default();
+    let reserve_msat = backend_fee_reserve(amount_msat);
+    if reserve_msat > max_fee_msat {
+        return Err(MokshaMintError::PayInvoice(
+            payment_request.to_owned(),
+            LightningError::FeeLimitExceeded(reserve_msat, max_fee_msat),
+        ));
+    }
+    Ok(())
+}
@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 
 use url::Url;
 
-use super::{error::LightningError, Lightning};
+use super::{error::LightningError, Lightning, PaymentStatus};
 use crate::{
     error::MokshaMintError,
     model::{CreateInvoiceParams, CreateInvoiceResult, PayInvoiceResult},
@@ -64,6 +64,40 @@ impl Lightning for StrikeLightning {
         Ok(self.client.is_invoice_paid(&invoice_id).await?)
     }
 
+    async fn get_payment_status(&self, payment_id: &str) -> Result<PaymentStatus, MokshaMintError> {
+        let payment = match self.client.get_ln_payment(payment_id).await {
+            // the payment quote may not have been executed yet
+            Err(LightningError::NotFound) => return Ok(PaymentStatus::Unknown),
+            result => result?,
+        };
+        Ok(match payment.state.as_str() {
+            "COMPLETED" => PaymentStatus::Paid(PayInvoiceResult {
+                payment_hash: payment_id.to_owned(),
+                payment_preimage: None, // strike doesn't return the preimage
+                total_fees: payment.fee_msat / 1_000,
+            }),
+            "FAILED" => PaymentStatus::Failed,
+            _ => PaymentStatus::Pending,
+        })
+    }
+
+    async fn create_payment(
+        &self,
+        payment_request: &str,
+        max_fee_msat: u64,
+    ) -> Result<Option<String>, MokshaMintError> {
+        let payment_quote = self.client.create_ln_payment_quote(payment_request).await?;
+
+        // the fee is known before the payment quote is executed, so a payment above the limit is never made
+        if payment_quote.fee_msat > max_fee_msat {
+            return Err(MokshaMintError::PayInvoice(
+                payment_request.to_owned(),
+                LightningError::FeeLimitExceeded(payment_quote.fee_msat, max_fee_msat),
+            ));
+        }
+        Ok(Some(payment_quote.id))
+    }
+
     async fn create_invoice(&self, amount: u64) -> Result<CreateInvoiceResult, MokshaMintError> {
         let strike_invoice_id = self
             .client
@@ -93,33 +127,41 @@ impl Lightning for StrikeLightning {
     async fn pay_invoice(
         &self,
         payment_request: String,
+        max_fee_msat: u64,
+        payment_id: Option<String>,
     ) -> Result<PayInvoiceResult, MokshaMintError> {
         // strike doesn't return the payment_hash so we have to read the invoice into a Bolt11 and extract it
         let invoice = self.decode_invoice(payment_request.clone()).await?;
-        let invoice2 = invoice.clone();
-        let payment_hash: &[u8] = invoice2.payment_hash().as_ref();
-
-        let payment_quote_id = self
-            .client
-            .create_ln_payment_quote(&invoice.into_signed_raw().to_string())
-            .await?;
+        let payment_hash: &[u8] = invoice.payment_hash().as_ref();
 
-        let payment_result = self
+        let payment_quote_id = match payment_id {
+            Some(payment_quote_id) => payment_quote_id,
+            None => self
+                .create_payment(&payment_request, max_fee_msat)
+                .await?
+                .expect("strike always returns a payment quote"),
+        };
+        let payment = self
             .client
             .execute_ln_payment_quote(&payment_quote_id)
-            .await?;
-
-        if !payment_result {
-            return Err(MokshaMintError::PayInvoice(
+            .await
+            .map_err(|err| MokshaMintError::PayInvoice(payment_request.clone(), err))?;
+
+        match payment.state.as_str() {
+            "COMPLETED" => Ok(PayInvoiceResult {
+                payment_hash: hex::encode(payment_hash),
+                payment_preimage: None, // strike doesn't return the preimage
+                total_fees: payment.fee_msat / 1_000,
+            }),
+            "FAILED" => Err(MokshaMintError::PayInvoice(
                 payment_request,
                 LightningError::PaymentFailed,
-            ));
+            )),
+            _ => Err(MokshaMintError::PayInvoice(
+                payment_request,
+                LightningError::PaymentPending,
+            )),
         }
-
-        Ok(PayInvoiceResult {
-            payment_hash: hex::encode(payment_hash),
-            total_fees: 0, // FIXME return fees for strike
-        })
     }
 }
 
@@ -135,6 +177,38 @@ fn format_as_uuid_string(bytes: &[u8]) -> String {
     )
 }
 
+/// A quote for a lightning payment, which has to be executed to pay the invoice
+#[derive(Debug, Clone)]
+pub struct LnPaymentQuote {
+    pub id: String,
+    pub fee_msat: u64,
+}
+
+/// An outgoing lightning payment, its id is the id of the executed payment quote
+#[derive(Debug, Clone)]
+pub struct LnPayment {
+    /// PENDING, COMPLETED or FAILED
+    pub state: String,
+    pub fee_msat: u64,
+}
+
+impl LnPayment {
+    fn from_response(response: &serde_json::Value) -> Self {
+        Self {
+            state: response["state"].as_str().unwrap_or_default().to_owned(),
+            fee_msat: btc_to_msat(&response["lightningNetworkFee"]),
+        }
+    }
+}
+
+/// Converts an amount of the api, a decimal string in BTC, to msat
+fn btc_to_msat(amount: &serde_json::Value) -> u64 {
+    amount["amount"]
+        .as_str()
+        .and_then(|amount| amount.parse::<f64>().ok())
+        .map_or(0, |amount| (amount * 100_000_000_000.0).round() as u64)
+}
+
 #[derive(Clone)]
 pub struct StrikeClient {
     api_key: String,
@@ -280,7 +354,10 @@ impl StrikeClient {
         Ok(payment_request)
     }
 
-    pub async fn create_ln_payment_quote(&self, bolt11: &str) -> Result<String, LightningError> {
+    pub async fn create_ln_payment_quote(
+        &self,
+        bolt11: &str,
+    ) -> Result<LnPaymentQuote, LightningError> {
         let params = serde_json::json!({
             "lnInvoice": bolt11,
             "sourceCurrency": "BTC",
@@ -292,22 +369,34 @@ impl StrikeClient {
             )
             .await?;
         let response: serde_json::Value = serde_json::from_str(&body)?;
-        let payment_quote_id = response["paymentQuoteId"]
+        let id = response["paymentQuoteId"]
             .as_str()
             .expect("paymentQuoteId is empty")
             .to_owned();
+        let fee_msat = btc_to_msat(&response["lightningNetworkFee"]);
 
-        Ok(payment_quote_id)
+        Ok(LnPaymentQuote { id, fee_msat })
     }
 
-    pub async fn execute_ln_payment_quote(&self, quote_id: &str) -> Result<bool, LightningError> {
+    pub async fn execute_ln_payment_quote(
+        &self,
+        quote_id: &str,
+    ) -> Result<LnPayment, LightningError> {
         let endpoint = format!("v1/payment-quotes/{}/execute", quote_id);
         let body = self
             .make_patch(&endpoint, &serde_json::to_string(&serde_json::json!({}))?)
             .await?;
         let response: serde_json::Value = serde_json::from_str(&body)?;
 
-        Ok(response["state"].as_str().unwrap_or("") == "COMPLETED")
+        Ok(LnPayment::from_response(&response))
+    }
+
+    /// Returns the payment that was made by executing the payment quote with the given id
+    pub async fn get_ln_payment(&self, payment_id: &str) -> Result<LnPayment, LightningError> {
+        let body = self.make_get(&format!("v1/payments/{payment_id}")).await?;
+        let response: serde_json::Value = serde_json::from_str(&body)?;
+
+        Ok(LnPayment::from_response(&response))
     }
 
     pub async fn is_invoice_paid(&self, invoice_id: &str) -> Result<bool, LightningError> {
@@ -1,3 +1,4 @@
+use moksha_core::primitives::{CurrencyUnit, MeltQuoteState};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -21,9 +22,11 @@ pub struct CreateInvoiceResult {
     pub payment_request: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PayInvoiceResult {
     pub payment_hash: String,
+    /// hex encoded preimage, if the backend returns it
+    pub payment_preimage: Option<String>,
     /// total fees in sat
     pub total_fees: u64,
 }
@@ -37,3 +40,131 @@ pub struct CreateInvoiceParams {
     pub webhook: Option<String>,
     pub internal: Option<bool>,
 }
+
+/// A keyset of the mint as stored in the keyset registry.
+/// The keys are derived from the private key of the mint and the derivation path.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KeysetInfo {
+    pub id: String,
+    pub unit: CurrencyUnit,
+    pub derivation_path: String,
+    pub active: bool,
+    pub input_fee_ppk: u64,
+}
+
+/// The ledger of a keyset: the ecash that was issued and redeemed with the keyset since the ledger was introduced.
+/// All amounts are in the unit of the keyset.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Liabilities {
+    pub keyset_id: String,
+    pub unit: CurrencyUnit,
+    /// issued for paid mint quotes
+    pub minted: u64,
+    /// redeemed by melts
+    pub melted: u64,
+    /// issued as change of melts
+    pub melt_change: u64,
+    /// redeemed by swaps
+    pub swapped_in: u64,
+    /// issued by swaps
+    pub swapped_out: u64,
+    /// input fees and unused fee reserves that were kept by the mint
+    pub fees: u64,
+}
+
+impl Liabilities {
+    pub fn new(keyset_id: impl Into<String>, unit: CurrencyUnit) -> Self {
+        Self {
+            keyset_id: keyset_id.into(),
+            unit,
+            minted: 0,
+            melted: 0,
+            melt_change: 0,
+            swapped_in: 0,
+            swapped_out: 0,
+            fees: 0,
+        }
+    }
+
+    /// the ecash of the keyset that was issued and not redeemed yet
+    pub fn outstanding(&self) -> i64 {
+        (self.minted + self.melt_change + self.swapped_out) as i64
+            - (self.melted + self.swapped_in) as i64
+    }
+}
+
+/// The outstanding ecash of all keysets of a unit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UnitLiabilities {
+    pub unit: CurrencyUnit,
+    pub outstanding: i64,
+    pub fees: u64,
+}
+
+/// The balances of the backends of the mint
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BackendBalances {
+    /// balance of the lightning backend, None if the backend can't report it or is unreachable
+    pub lightning_balance_msat: Option<u64>,
+    /// balance of the onchain backend, None if there is no onchain backend or it can't report its balance
+    pub onchain_balance_sat: Option<u64>,
+}
+
+/// The outstanding ecash of the mint compared to the funds of its backends
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LiabilitiesReport {
+    pub keysets: Vec<Liabilities>,
+    pub units: Vec<UnitLiabilities>,
+    /// balance of the lightning backend, None if the backend can't report it or is unreachable
+    pub lightning_balance_msat: Option<u64>,
+    /// balance of the onchain backend, None if there is no onchain backend or it can't report its balance
+    pub onchain_balance_sat: Option<u64>,
+    /// the backend balances minus the outstanding ecash, other units than sat and msat are converted with the price
+    /// oracle. A negative surplus means that the mint can't redeem all of its ecash. None if no backend reports its
+    /// balance or a unit can't be priced.
+    pub surplus_msat: Option<i64>,
+}
+
+/// Conditions of the bolt11 mint quotes that are listed by the admin api, unset conditions match every quote
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MintQuoteFilter {
+    /// the quote id, the payment hash or a part of the payment request
+    pub search: Option<String>,
+    pub unit: Option<CurrencyUnit>,
+    pub paid: Option<bool>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+/// Conditions of the bolt11 melt quotes that are listed by the admin api, unset conditions match every quote
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MeltQuoteFilter {
+    /// the quote id or a part of the payment request
+    pub search: Option<String>,
+    pub unit: Option<CurrencyUnit>,
+    pub state: Option<MeltQuoteState>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+/// Replaces the active keyset of the unit with a new one
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RotateKeysetRequest {
+    pub unit: CurrencyUnit,
+    /// the input fee of the new keyset, defaults to the input fee of the mint config
+    pub input_fee_ppk: Option<u64>,
+}
+
+/// Whether new mints and melts are rejected, as announced by `Nut4.disabled` and `Nut5.disabled` of the mint info
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MintOperations {
+    pub minting_disabled: bool,
+    pub melting_disabled: bool,
+}
+
+/// Changes the operations of the mint, unset fields are kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UpdateMintOperations {
+    pub minting_disabled: Option<bool>,
+    pub melting_disabled: Option<bool>,
+}
@@ -0,0 +1,95 @@
+//! This module defines the exchange rate oracle that is used to convert between non-bitcoin currency units and msat.
+//!
+//! The mint creates a keyset for every unit the oracle can price (e.g. `usd` in cents). Quotes in these units are
+//! converted with the current price when an invoice is created or a payment request is decoded.
+
+use std::{collections::HashMap, path::Path};
+
+use async_trait::async_trait;
+use moksha_core::primitives::CurrencyUnit;
+
+#[cfg(test)]
+use mockall::automock;
+
+use crate::error::MokshaMintError;
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait PriceOracle: Send + Sync {
+    /// Returns the non-bitcoin units this oracle can price
+    fn units(&self) -> Vec<CurrencyUnit>;
+
+    /// Returns the price of one unit (e.g. one usd cent) in msat
+    async fn msat_per_unit(&self, unit: &CurrencyUnit) -> Result<u64, MokshaMintError>;
+}
+
+/// A price oracle with fixed exchange rates, which can be read from a json file like `{"usd": 15000}`
+#[derive(Debug, Clone, Default)]
+pub struct FixedPriceOracle {
+    rates: HashMap<CurrencyUnit, u64>,
+}
+
+impl FixedPriceOracle {
+    pub fn new(rates: HashMap<CurrencyUnit, u64>) -> Result<Self, MokshaMintError> {
+        if let Some((unit, _)) = rates.iter().find(|(_, rate)| **rate == 0) {
+            return Err(MokshaMintError::PriceOracle(format!(
+                "rate for {unit} must be greater than 0"
+            )));
+        }
+        Ok(Self { rates })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, MokshaMintError> {
+        let content = std::fs::read_to_string(path).map_err(|err| {
+            MokshaMintError::PriceOracle(format!("can not read {}: {err}", path.display()))
+        })?;
+        Self::new(serde_json::from_str(&content)?)
+    }
+}
+
+#[async_trait]
+impl PriceOracle for FixedPriceOracle {
+    fn units(&self) -> Vec<CurrencyUnit> {
+        self.rates
+            .keys()
+            .filter(|unit| !matches!(unit, CurrencyUnit::Sat | CurrencyUnit::MSat))
+            .cloned()
+            .collect()
+    }
+
+    async fn msat_per_unit(&self, unit: &CurrencyUnit) -> Result<u64, MokshaMintError> {
+        self.rates
+            .get(unit)
+            .copied()
+            .ok_or_else(|| MokshaMintError::CurrencyNotSupported(unit.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use moksha_core::primitives::CurrencyUnit;
+    use pretty_assertions::assert_eq;
+
+    use crate::price::{FixedPriceOracle, PriceOracle};
+
+    #[tokio::test]
+    async fn test_fixed_price_oracle_from_file() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("rates.json");
+        std::fs::write(&path, r#"{"usd": 15000}"#)?;
+
+        let oracle = FixedPriceOracle::from_file(&path)?;
+        assert_eq!(vec![CurrencyUnit::Usd], oracle.units());
+        assert_eq!(15_000, oracle.msat_per_unit(&CurrencyUnit::Usd).await?);
+        assert!(oracle.msat_per_unit(&CurrencyUnit::Sat).await.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixed_price_oracle_zero_rate() {
+        let result = FixedPriceOracle::new(HashMap::from([(CurrencyUnit::Usd, 0)]));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use std::str::FromStr;
+
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::header::AUTHORIZATION,
+    middleware::Next,
+    response::Response,
+    Json,
+};
+use moksha_core::primitives::{Bolt11MeltQuote, Bolt11MintQuote};
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::{
+    database::Database,
+    error::MokshaMintError,
+    mint::Mint,
+    model::{
+        BackendBalances, KeysetInfo, LiabilitiesReport, MeltQuoteFilter, MintOperations,
+        MintQuoteFilter, RotateKeysetRequest, UpdateMintOperations,
+    },
+};
+
+/// Rejects requests to the admin api that don't carry the admin token of the server config as bearer token
+pub async fn require_admin_token<DB: Database>(
+    State(mint): State<Mint<DB>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, MokshaMintError> {
+    let expected = mint
+        .config
+        .server
+        .admin_token
+        .as_deref()
+        .ok_or(MokshaMintError::Unauthorized)?;
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or(MokshaMintError::Unauthorized)?;
+    if !constant_time_eq(token.as_bytes(), expected.as_bytes()) {
+        return Err(MokshaMintError::Unauthorized);
+    }
+    Ok(next.run(request).await)
+}
+
+/// compares the tokens without returning early, so the admin token can't be guessed from the response time
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[instrument(name = "get_admin_mint_quotes_bolt11", skip(mint), err)]
+pub async fn get_mint_quotes_bolt11<DB: Database>(
+    State(mint): State<Mint<DB>>,
+    Query(filter): Query<MintQuoteFilter>,
+) -> Result<Json<Vec<Bolt11MintQuote>>, MokshaMintError> {
+    Ok(Json(mint.search_bolt11_mint_quotes(&filter).await?))
+}
+
+#[instrument(name = "get_admin_melt_quotes_bolt11", skip(mint), err)]
+pub async fn get_melt_quotes_bolt11<DB: Database>(
+    State(mint): State<Mint<DB>>,
+    Query(filter): Query<MeltQuoteFilter>,
+) -> Result<Json<Vec<Bolt11MeltQuote>>, MokshaMintError> {
+    Ok(Json(mint.search_bolt11_melt_quotes(&filter).await?))
+}
+
+#[instrument(name = "post_expire_mint_quote_bolt11", skip(mint), err)]
+pub async fn post_expire_mint_quote_bolt11<DB: Database>(
+    Path(quote_id): Path<String>,
+    State(mint): State<Mint<DB>>,
+) -> Result<Json<Bolt11MintQuote>, MokshaMintError> {
+    let quote = mint
+        .expire_bolt11_mint_quote(&Uuid::from_str(&quote_id)?)
+        .await?;
+    info!("admin expired mint quote {quote_id}");
+    Ok(Json(quote))
+}
+
+#[instrument(name = "post_expire_melt_quote_bolt11", skip(mint), err)]
+pub async fn post_expire_melt_quote_bolt11<DB: Database>(
+    Path(quote_id): Path<String>,
+    State(mint): State<Mint<DB>>,
+) -> Result<Json<Bolt11MeltQuote>, MokshaMintError> {
+    let quote = mint
+        .expire_bolt11_melt_quote(&Uuid::from_str(&quote_id)?)
+        .await?;
+    info!("admin expired melt quote {quote_id}");
+    Ok(Json(quote))
+}
+
+#[instrument(name = "post_rotate_keyset", skip(mint), err)]
+pub async fn post_rotate_keyset<DB: Database>(
+    State(mint): State<Mint<DB>>,
+    Json(request): Json<RotateKeysetRequest>,
+) -> Result<Json<KeysetInfo>, MokshaMintError> {
+    // only a unit that already has an active keyset can get a new one
+    mint.verify_unit_supported(&request.unit)?;
+    let info = mint
+        .rotate_keyset(
+            request.unit,
+            request.input_fee_ppk.unwrap_or(mint.config.input_fee_ppk),
+        )
+        .await?;
+    info!("admin rotated the {} keyset to {}", info.unit, info.id);
+    Ok(Json(info))
+}
+
+#[instrument(name = "get_operations", skip(mint), err)]
+pub async fn get_operations<DB: Database>(
+    State(mint): State<Mint<DB>>,
+) -> Result<Json<MintOperations>, MokshaMintError> {
+    Ok(Json(mint.operations()))
+}
+
+#[instrument(name = "put_operations", skip(mint), err)]
+pub async fn put_operations<DB: Database>(
+    State(mint): State<Mint<DB>>,
+    Json(update): Json<UpdateMintOperations>,
+) -> Result<Json<MintOperations>, MokshaMintError> {
+    let operations = mint.update_operations(&update);
+    info!("admin changed the mint operations to {operations:?}");
+    Ok(Json(operations))
+}
+
+#[instrument(name = "get_balances", skip(mint), err)]
+pub async fn get_balances<DB: Database>(
+    State(mint): State<Mint<DB>>,
+) -> Result<Json<BackendBalances>, MokshaMintError> {
+    Ok(Json(mint.backend_balances().await))
+}
+
+#[instrument(name = "get_liabilities", skip(mint), err)]
+pub async fn get_liabilities<DB: Database>(
+    State(mint): State<Mint<DB>>,
+) -> Result<Json<LiabilitiesReport>, MokshaMintError> {
+    Ok(Json(mint.liabilities_report().await?))
+}
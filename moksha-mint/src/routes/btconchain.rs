@@ -12,7 +12,10 @@ use tracing::{info, instrument};
 use uuid::Uuid;
 
 use crate::database::Database;
-use crate::{error::MokshaMintError, mint::Mint};
+use crate::{
+    error::MokshaMintError,
+    mint::{verify_not_expired, Mint},
+};
 use chrono::{Duration, Utc};
 use std::str::FromStr;
 
@@ -25,10 +28,11 @@ use std::str::FromStr;
         ),
     )]
 #[instrument(name = "post_mint_quote_btconchain", skip(mint), err)]
-pub async fn post_mint_quote_btconchain(
-    State(mint): State<Mint>,
+pub async fn post_mint_quote_btconchain<DB: Database>(
+    State(mint): State<Mint<DB>>,
     Json(request): Json<PostMintQuoteBtcOnchainRequest>,
 ) -> Result<Json<PostMintQuoteBtcOnchainResponse>, MokshaMintError> {
+    mint.verify_minting_enabled()?;
     let onchain_config = mint.config.btconchain_backend.unwrap_or_default();
 
     if request.unit != CurrencyUnit::Sat {
@@ -83,9 +87,9 @@ pub async fn post_mint_quote_btconchain(
         )
     )]
 #[instrument(name = "get_mint_quote_btconchain", skip(mint), err)]
-pub async fn get_mint_quote_btconchain(
+pub async fn get_mint_quote_btconchain<DB: Database>(
     Path(quote_id): Path<String>,
-    State(mint): State<Mint>,
+    State(mint): State<Mint<DB>>,
 ) -> Result<Json<PostMintQuoteBtcOnchainResponse>, MokshaMintError> {
     info!("get_quote onchain: {}", quote_id);
 
@@ -127,33 +131,53 @@ pub async fn get_mint_quote_btconchain(
         ),
     )]
 #[instrument(name = "post_mint_btconchain", skip(mint), err)]
-pub async fn post_mint_btconchain(
-    State(mint): State<Mint>,
+pub async fn post_mint_btconchain<DB: Database>(
+    State(mint): State<Mint<DB>>,
     Json(request): Json<PostMintBtcOnchainRequest>,
 ) -> Result<Json<PostMintBtcOnchainResponse>, MokshaMintError> {
     let mut tx = mint.db.begin_tx().await?;
+    mint.verify_unit(
+        request.outputs.iter().map(|output| &output.id),
+        Some(&CurrencyUnit::Sat),
+    )?;
+    let quote = mint
+        .db
+        .get_onchain_mint_quote(&mut tx, &Uuid::from_str(request.quote.as_str())?)
+        .await?;
+    // a deposit that was made before the quote expired can still be minted
+    if let Err(err) = verify_not_expired(&quote.quote_id, quote.expiry) {
+        let min_confs = mint
+            .config
+            .btconchain_backend
+            .clone()
+            .unwrap_or_default()
+            .min_confirmations;
+        if !mint
+            .onchain
+            .as_ref()
+            .expect("onchain backend not configured")
+            .is_paid(&quote.address, quote.amount, min_confs)
+            .await?
+        {
+            return Err(err);
+        }
+    }
     let signatures = mint
         .mint_tokens(
             &mut tx,
             PaymentMethod::BtcOnchain,
             request.quote.clone(),
             &request.outputs,
-            &mint.keyset,
             false,
         )
         .await?;
 
-    let old_quote = &mint
-        .db
-        .get_onchain_mint_quote(&mut tx, &Uuid::from_str(request.quote.as_str())?)
-        .await?;
-
     mint.db
         .update_onchain_mint_quote(
             &mut tx,
             &BtcOnchainMintQuote {
                 state: MintBtcOnchainState::Issued,
-                ..old_quote.clone()
+                ..quote
             },
         )
         .await?;
@@ -170,10 +194,11 @@ pub async fn post_mint_btconchain(
         ),
     )]
 #[instrument(name = "post_melt_quote_btconchain", skip(mint), err)]
-pub async fn post_melt_quote_btconchain(
-    State(mint): State<Mint>,
+pub async fn post_melt_quote_btconchain<DB: Database>(
+    State(mint): State<Mint<DB>>,
     Json(melt_request): Json<PostMeltQuoteBtcOnchainRequest>,
 ) -> Result<Json<Vec<PostMeltQuoteBtcOnchainResponse>>, MokshaMintError> {
+    mint.verify_melting_enabled()?;
     let PostMeltQuoteBtcOnchainRequest {
         address,
         amount,
@@ -238,9 +263,9 @@ pub async fn post_melt_quote_btconchain(
         )
     )]
 #[instrument(name = "get_melt_quote_btconchain", skip(mint), err)]
-pub async fn get_melt_quote_btconchain(
+pub async fn get_melt_quote_btconchain<DB: Database>(
     Path(quote_id): Path<String>,
-    State(mint): State<Mint>,
+    State(mint): State<Mint<DB>>,
 ) -> Result<Json<PostMeltQuoteBtcOnchainResponse>, MokshaMintError> {
     info!("get_melt_quote onchain: {}", quote_id);
     let mut tx = mint.db.begin_tx().await?;
@@ -280,8 +305,8 @@ pub async fn get_melt_quote_btconchain(
         ),
     )]
 #[instrument(name = "post_melt_btconchain", skip(mint), err)]
-pub async fn post_melt_btconchain(
-    State(mint): State<Mint>,
+pub async fn post_melt_btconchain<DB: Database>(
+    State(mint): State<Mint<DB>>,
     Json(melt_request): Json<PostMeltBtcOnchainRequest>,
 ) -> Result<Json<PostMeltBtcOnchainResponse>, MokshaMintError> {
     let mut tx = mint.db.begin_tx().await?;
@@ -316,8 +341,8 @@ pub async fn post_melt_btconchain(
     }))
 }
 
-async fn is_onchain_paid(
-    mint: &Mint,
+async fn is_onchain_paid<DB: Database>(
+    mint: &Mint<DB>,
     quote: &BtcOnchainMeltQuote,
 ) -> Result<bool, MokshaMintError> {
     let min_confs = mint
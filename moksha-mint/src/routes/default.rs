@@ -3,14 +3,18 @@ use axum::{
     Json,
 };
 use moksha_core::{
-    keyset::Keysets,
+    blind::TotalAmount,
+    keyset::{Keyset, Keysets},
     primitives::{
-        Bolt11MeltQuote, Bolt11MintQuote, CurrencyUnit, KeyResponse, KeysResponse,
-        MintInfoResponse, Nuts, PaymentMethod, PostMeltBolt11Request, PostMeltBolt11Response,
-        PostMeltQuoteBolt11Request, PostMeltQuoteBolt11Response, PostMintBolt11Request,
-        PostMintBolt11Response, PostMintQuoteBolt11Request, PostMintQuoteBolt11Response,
-        PostSwapRequest, PostSwapResponse,
+        Bolt11MeltQuote, Bolt11MintQuote, CurrencyUnit, KeyResponse, KeysResponse, MeltQuoteState,
+        MintInfoResponse, Nut17, Nut17Supported, Nut4, Nut5, Nuts, PaymentMethod,
+        PaymentMethodConfig, PostCheckStateRequest, PostCheckStateResponse, PostMeltBolt11Request,
+        PostMeltBolt11Response, PostMeltQuoteBolt11Request, PostMeltQuoteBolt11Response,
+        PostMintBolt11Request, PostMintBolt11Response, PostMintQuoteBolt11Request,
+        PostMintQuoteBolt11Response, PostRestoreRequest, PostRestoreResponse, PostSwapRequest,
+        PostSwapResponse,
     },
+    subscription::SubscriptionKind,
 };
 use tracing::{debug, instrument};
 use uuid::Uuid;
@@ -19,7 +23,8 @@ use crate::database::Database;
 use crate::{
     config::{BtcOnchainConfig, MintConfig},
     error::MokshaMintError,
-    mint::Mint,
+    mint::{verify_not_expired, Mint},
+    model::MintOperations,
 };
 use chrono::{Duration, Utc};
 use std::str::FromStr;
@@ -33,12 +38,12 @@ use std::str::FromStr;
         ),
     )]
 #[instrument(name = "post_swap", skip(mint), err)]
-pub async fn post_swap(
-    State(mint): State<Mint>,
+pub async fn post_swap<DB: Database>(
+    State(mint): State<Mint<DB>>,
     Json(swap_request): Json<PostSwapRequest>,
 ) -> Result<Json<PostSwapResponse>, MokshaMintError> {
     let response = mint
-        .swap(&swap_request.inputs, &swap_request.outputs, &mint.keyset)
+        .swap(&swap_request.inputs, &swap_request.outputs)
         .await?;
 
     Ok(Json(PostSwapResponse {
@@ -46,6 +51,43 @@ pub async fn post_swap(
     }))
 }
 
+#[utoipa::path(
+        post,
+        path = "/v1/checkstate",
+        request_body = PostCheckStateRequest,
+        responses(
+            (status = 200, description = "post checkstate", body = [PostCheckStateResponse])
+        ),
+    )]
+#[instrument(name = "post_checkstate", skip(mint), err)]
+pub async fn post_checkstate<DB: Database>(
+    State(mint): State<Mint<DB>>,
+    Json(request): Json<PostCheckStateRequest>,
+) -> Result<Json<PostCheckStateResponse>, MokshaMintError> {
+    let states = mint.check_state(&request.ys).await?;
+    Ok(Json(PostCheckStateResponse { states }))
+}
+
+#[utoipa::path(
+        post,
+        path = "/v1/restore",
+        request_body = PostRestoreRequest,
+        responses(
+            (status = 200, description = "post restore", body = [PostRestoreResponse])
+        ),
+    )]
+#[instrument(name = "post_restore", skip(mint), err)]
+pub async fn post_restore<DB: Database>(
+    State(mint): State<Mint<DB>>,
+    Json(request): Json<PostRestoreRequest>,
+) -> Result<Json<PostRestoreResponse>, MokshaMintError> {
+    let (outputs, signatures) = mint.restore(&request.outputs).await?;
+    Ok(Json(PostRestoreResponse {
+        outputs,
+        signatures,
+    }))
+}
+
 #[utoipa::path(
         get,
         path = "/v1/keys",
@@ -54,13 +96,20 @@ pub async fn post_swap(
         )
     )]
 #[instrument(skip(mint), err)]
-pub async fn get_keys(State(mint): State<Mint>) -> Result<Json<KeysResponse>, MokshaMintError> {
+pub async fn get_keys<DB: Database>(
+    State(mint): State<Mint<DB>>,
+) -> Result<Json<KeysResponse>, MokshaMintError> {
     Ok(Json(KeysResponse {
-        keysets: vec![KeyResponse {
-            id: mint.keyset.keyset_id.clone(),
-            unit: CurrencyUnit::Sat,
-            keys: mint.keyset.public_keys,
-        }],
+        keysets: mint
+            .keysets()
+            .into_iter()
+            .filter(|entry| entry.info.active)
+            .map(|entry| KeyResponse {
+                id: entry.info.id,
+                unit: entry.info.unit,
+                keys: entry.keyset.public_keys,
+            })
+            .collect(),
     }))
 }
 
@@ -75,19 +124,17 @@ pub async fn get_keys(State(mint): State<Mint>) -> Result<Json<KeysResponse>, Mo
         )
     )]
 #[instrument(skip(mint), err)]
-pub async fn get_keys_by_id(
+pub async fn get_keys_by_id<DB: Database>(
     Path(id): Path<String>,
-    State(mint): State<Mint>,
+    State(mint): State<Mint<DB>>,
 ) -> Result<Json<KeysResponse>, MokshaMintError> {
-    if id != mint.keyset.keyset_id {
-        return Err(MokshaMintError::KeysetNotFound(id));
-    }
+    let entry = mint.keyset(&id)?;
 
     Ok(Json(KeysResponse {
         keysets: vec![KeyResponse {
-            id: mint.keyset.keyset_id.clone(),
-            unit: CurrencyUnit::Sat,
-            keys: mint.keyset.public_keys,
+            id: entry.info.id,
+            unit: entry.info.unit,
+            keys: entry.keyset.public_keys,
         }],
     }))
 }
@@ -100,12 +147,21 @@ pub async fn get_keys_by_id(
         ),
     )]
 #[instrument(skip(mint), err)]
-pub async fn get_keysets(State(mint): State<Mint>) -> Result<Json<Keysets>, MokshaMintError> {
-    Ok(Json(Keysets::new(
-        mint.keyset.keyset_id,
-        CurrencyUnit::Sat,
-        true,
-    )))
+pub async fn get_keysets<DB: Database>(
+    State(mint): State<Mint<DB>>,
+) -> Result<Json<Keysets>, MokshaMintError> {
+    Ok(Json(Keysets {
+        keysets: mint
+            .keyset_infos()
+            .into_iter()
+            .map(|info| Keyset {
+                id: info.id,
+                unit: info.unit,
+                active: info.active,
+                input_fee_ppk: info.input_fee_ppk,
+            })
+            .collect(),
+    }))
 }
 
 #[utoipa::path(
@@ -117,17 +173,23 @@ pub async fn get_keysets(State(mint): State<Mint>) -> Result<Json<Keysets>, Moks
         ),
     )]
 #[instrument(name = "post_mint_quote_bolt11", skip(mint), err)]
-pub async fn post_mint_quote_bolt11(
-    State(mint): State<Mint>,
+pub async fn post_mint_quote_bolt11<DB: Database>(
+    State(mint): State<Mint<DB>>,
     Json(request): Json<PostMintQuoteBolt11Request>,
 ) -> Result<Json<PostMintQuoteBolt11Response>, MokshaMintError> {
-    // FIXME check currency unit
+    mint.verify_minting_enabled()?;
+    mint.verify_unit_supported(&request.unit)?;
+    let amount_msat = mint.to_msat(request.amount, &request.unit).await?;
+    let amount_sat = amount_msat.div_ceil(1_000);
+
     let key = Uuid::new_v4();
-    let (pr, _hash) = mint.create_invoice(key.to_string(), request.amount).await?;
+    let (pr, _hash) = mint.create_invoice(key.to_string(), amount_sat).await?;
 
     let quote = Bolt11MintQuote {
         quote_id: key,
         payment_request: pr.clone(),
+        amount: request.amount,
+        unit: request.unit,
         expiry: quote_expiry(), // FIXME use timestamp type in DB
         paid: false,
     };
@@ -150,37 +212,56 @@ pub async fn post_mint_quote_bolt11(
         )
     )]
 #[instrument(name = "post_mint_bolt11", fields(quote_id = %request.quote), skip_all, err)]
-pub async fn post_mint_bolt11(
-    State(mint): State<Mint>,
+pub async fn post_mint_bolt11<DB: Database>(
+    State(mint): State<Mint<DB>>,
     Json(request): Json<PostMintBolt11Request>,
 ) -> Result<Json<PostMintBolt11Response>, MokshaMintError> {
     let mut tx = mint.db.begin_tx().await?;
+    let old_quote = &mint
+        .db
+        .get_bolt11_mint_quote(&mut tx, &Uuid::from_str(request.quote.as_str())?)
+        .await?;
+
+    // an invoice that was paid before the quote expired can still be minted
+    if let Err(err) = verify_not_expired(&old_quote.quote_id, old_quote.expiry) {
+        if !old_quote.paid
+            && !mint
+                .lightning
+                .is_invoice_paid(old_quote.payment_request.clone())
+                .await?
+        {
+            return Err(err);
+        }
+    }
+    mint.verify_unit(
+        request.outputs.iter().map(|output| &output.id),
+        Some(&old_quote.unit),
+    )?;
+    let outputs_amount = request.outputs.total_amount();
+    if outputs_amount > old_quote.amount {
+        return Err(MokshaMintError::InvalidAmount(format!(
+            "outputs amount {outputs_amount} exceeds quote amount {}",
+            old_quote.amount
+        )));
+    }
+
     let signatures = mint
         .mint_tokens(
             &mut tx,
             PaymentMethod::Bolt11,
             request.quote.clone(),
             &request.outputs,
-            &mint.keyset,
             false,
         )
         .await?;
 
-    let old_quote = &mint
-        .db
-        .get_bolt11_mint_quote(&mut tx, &Uuid::from_str(request.quote.as_str())?)
-        .await?;
-
-    mint.db
-        .update_bolt11_mint_quote(
-            &mut tx,
-            &Bolt11MintQuote {
-                paid: true,
-                ..old_quote.clone()
-            },
-        )
-        .await?;
+    let quote = Bolt11MintQuote {
+        paid: true,
+        ..old_quote.clone()
+    };
+    mint.db.update_bolt11_mint_quote(&mut tx, &quote).await?;
     tx.commit().await?;
+    mint.publish_mint_quote(&quote);
     Ok(Json(PostMintBolt11Response { signatures }))
 }
 
@@ -193,29 +274,36 @@ pub async fn post_mint_bolt11(
         ),
     )]
 #[instrument(name = "post_melt_quote_bolt11", skip(mint), err)]
-pub async fn post_melt_quote_bolt11(
-    State(mint): State<Mint>,
+pub async fn post_melt_quote_bolt11<DB: Database>(
+    State(mint): State<Mint<DB>>,
     Json(melt_request): Json<PostMeltQuoteBolt11Request>,
 ) -> Result<Json<PostMeltQuoteBolt11Response>, MokshaMintError> {
+    mint.verify_melting_enabled()?;
+    mint.verify_unit_supported(&melt_request.unit)?;
     let invoice = mint
         .lightning
         .decode_invoice(melt_request.request.clone())
         .await?;
-    let amount = invoice.amount_milli_satoshis().ok_or_else(|| {
+    let amount_msat = invoice.amount_milli_satoshis().ok_or_else(|| {
         crate::error::MokshaMintError::InvalidAmount("invalid invoice".to_owned())
     })?;
-    let fee_reserve = mint.fee_reserve(amount) / 1_000; // FIXME check if this is correct
-    debug!("fee_reserve: {}", fee_reserve);
+    let amount = mint.from_msat(amount_msat, &melt_request.unit).await?;
+    let fee_reserve = mint
+        .from_msat(mint.fee_reserve(amount_msat), &melt_request.unit)
+        .await?;
+    debug!("fee_reserve: {} {}", fee_reserve, melt_request.unit);
 
-    let amount_sat = amount / 1_000;
     let key = Uuid::new_v4();
     let quote = Bolt11MeltQuote {
         quote_id: key,
-        amount: amount_sat,
+        amount,
         fee_reserve,
+        unit: melt_request.unit,
         expiry: quote_expiry(),
         payment_request: melt_request.request.clone(),
-        paid: false,
+        state: MeltQuoteState::Unpaid,
+        payment_preimage: None,
+        fee_paid: None,
     };
     let mut tx = mint.db.begin_tx().await?;
     mint.db.add_bolt11_melt_quote(&mut tx, &quote).await?;
@@ -239,8 +327,8 @@ fn quote_expiry() -> u64 {
         ),
     )]
 #[instrument(name = "post_melt_bolt11", skip(mint), err)]
-pub async fn post_melt_bolt11(
-    State(mint): State<Mint>,
+pub async fn post_melt_bolt11<DB: Database>(
+    State(mint): State<Mint<DB>>,
     Json(melt_request): Json<PostMeltBolt11Request>,
 ) -> Result<Json<PostMeltBolt11Response>, MokshaMintError> {
     let mut tx = mint.db.begin_tx().await?;
@@ -251,24 +339,17 @@ pub async fn post_melt_bolt11(
 
     debug!("post_melt_bolt11 fee_reserve: {:#?}", &quote);
 
-    let (paid, payment_preimage, change) = mint
-        .melt_bolt11(
-            &mut tx,
-            quote.payment_request.to_owned(),
-            quote.fee_reserve,
-            &melt_request.inputs,
-            melt_request.outputs,
-            &mint.keyset,
-        )
-        .await?;
-    mint.db
-        .update_bolt11_melt_quote(&mut tx, &Bolt11MeltQuote { paid, ..quote })
+    let (quote, change) = mint
+        .melt_bolt11(&mut tx, &quote, &melt_request.inputs, melt_request.outputs)
         .await?;
     tx.commit().await?;
+    mint.publish_melt_bolt11(&quote, &melt_request.inputs)
+        .await?;
 
     Ok(Json(PostMeltBolt11Response {
-        paid,
-        payment_preimage: Some(payment_preimage),
+        paid: quote.state == MeltQuoteState::Paid,
+        state: quote.state,
+        payment_preimage: quote.payment_preimage,
         change,
     }))
 }
@@ -284,25 +365,16 @@ pub async fn post_melt_bolt11(
         )
     )]
 #[instrument(name = "get_mint_quote_bolt11", skip(mint), err)]
-pub async fn get_mint_quote_bolt11(
+pub async fn get_mint_quote_bolt11<DB: Database>(
     Path(quote_id): Path<String>,
-    State(mint): State<Mint>,
+    State(mint): State<Mint<DB>>,
 ) -> Result<Json<PostMintQuoteBolt11Response>, MokshaMintError> {
     debug!("get_quote: {}", quote_id);
 
-    let mut tx = mint.db.begin_tx().await?;
     let quote = mint
-        .db
-        .get_bolt11_mint_quote(&mut tx, &Uuid::from_str(quote_id.as_str())?)
-        .await?;
-    tx.commit().await?;
-
-    let paid = mint
-        .lightning
-        .is_invoice_paid(quote.payment_request.clone())
+        .get_bolt11_mint_quote(&Uuid::from_str(quote_id.as_str())?)
         .await?;
-
-    Ok(Json(Bolt11MintQuote { paid, ..quote }.into()))
+    Ok(Json(quote.into()))
 }
 
 #[utoipa::path(
@@ -316,9 +388,9 @@ pub async fn get_mint_quote_bolt11(
         )
     )]
 #[instrument(name = "get_melt_quote_bolt11", skip(mint), err)]
-pub async fn get_melt_quote_bolt11(
+pub async fn get_melt_quote_bolt11<DB: Database>(
     Path(quote_id): Path<String>,
-    State(mint): State<Mint>,
+    State(mint): State<Mint<DB>>,
 ) -> Result<Json<PostMeltQuoteBolt11Response>, MokshaMintError> {
     debug!("get_melt_quote: {}", quote_id);
     let mut tx = mint.db.begin_tx().await?;
@@ -328,7 +400,6 @@ pub async fn get_melt_quote_bolt11(
         .await?;
 
     tx.commit().await?;
-    // FIXME check for paid?
     Ok(Json(quote.into()))
 }
 
@@ -340,14 +411,28 @@ pub async fn get_melt_quote_bolt11(
         )
     )]
 #[instrument(name = "get_info", skip(mint), err)]
-pub async fn get_info(State(mint): State<Mint>) -> Result<Json<MintInfoResponse>, MokshaMintError> {
+pub async fn get_info<DB: Database>(
+    State(mint): State<Mint<DB>>,
+) -> Result<Json<MintInfoResponse>, MokshaMintError> {
     let mint_info = mint.config.info.clone();
     let contact = Some(mint_info.into());
+    let pubkey = mint.active_keyset().mint_pubkey;
+    let mut units = vec![];
+    for info in mint.keyset_infos().into_iter().filter(|info| info.active) {
+        if !units.contains(&info.unit) {
+            units.push(info.unit);
+        }
+    }
 
     let mint_info = MintInfoResponse {
-        nuts: get_nuts(&mint.config),
+        nuts: get_nuts(
+            &mint.config,
+            &units,
+            &mint.operations(),
+            mint.is_invoice_subscription_active(),
+        ),
         name: mint.config.info.name,
-        pubkey: mint.keyset.mint_pubkey,
+        pubkey,
         version: match mint.config.info.version {
             true => Some(mint.build_params.full_version()),
             _ => None,
@@ -360,10 +445,55 @@ pub async fn get_info(State(mint): State<Mint>) -> Result<Json<MintInfoResponse>
     Ok(Json(mint_info))
 }
 
-fn get_nuts(cfg: &MintConfig) -> Nuts {
+fn get_nuts(
+    cfg: &MintConfig,
+    units: &[CurrencyUnit],
+    operations: &MintOperations,
+    invoice_subscription_active: bool,
+) -> Nuts {
     let default_config = BtcOnchainConfig::default();
     let config = cfg.btconchain_backend.as_ref().unwrap_or(&default_config);
+    let mut nut4 = Nut4 {
+        disabled: operations.minting_disabled,
+        ..Nut4::default()
+    };
+    let mut nut5 = Nut5 {
+        disabled: operations.melting_disabled,
+        ..Nut5::default()
+    };
+    for unit in units.iter().filter(|unit| **unit != CurrencyUnit::Sat) {
+        nut4.payment_methods.push(PaymentMethodConfig {
+            unit: unit.clone(),
+            ..nut4.payment_methods[0].clone()
+        });
+        nut5.payment_methods.push(PaymentMethodConfig {
+            unit: unit.clone(),
+            ..nut5.payment_methods[0].clone()
+        });
+    }
+    // the subscriptions of NUT-17 are supported for every active unit. Paid mint quotes are only pushed while the
+    // backend streams the settlements of invoices, otherwise nobody checks the invoices of unpaid quotes
+    let mut commands = vec![
+        SubscriptionKind::Bolt11MeltQuote,
+        SubscriptionKind::ProofState,
+    ];
+    if invoice_subscription_active {
+        commands.insert(0, SubscriptionKind::Bolt11MintQuote);
+    }
+    let nut17 = Nut17 {
+        supported: units
+            .iter()
+            .map(|unit| Nut17Supported {
+                payment_method: PaymentMethod::Bolt11,
+                unit: unit.clone(),
+                commands: commands.clone(),
+            })
+            .collect(),
+    };
     Nuts {
+        nut4,
+        nut5,
+        nut17: Some(nut17),
         nut18: Some(config.to_owned().into()),
         nut19: Some(config.to_owned().into()),
         ..Nuts::default()
@@ -1,2 +1,4 @@
+pub mod admin;
 pub mod btconchain;
 pub mod default;
+pub mod ws;
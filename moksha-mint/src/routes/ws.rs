@@ -0,0 +1,239 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        State, WebSocketUpgrade,
+    },
+    response::IntoResponse,
+};
+use moksha_core::{
+    primitives::ProofState,
+    subscription::{
+        NotificationPayload, SubscribeParams, SubscriptionKind, UnsubscribeParams, WsMethod,
+        WsNotification, WsRequest, WsResponse, INVALID_PARAMS, INVALID_REQUEST, JSONRPC_VERSION,
+        PARSE_ERROR,
+    },
+};
+use secp256k1::PublicKey;
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, instrument, warn};
+use uuid::Uuid;
+
+use crate::{database::Database, error::MokshaMintError, mint::Mint};
+
+#[utoipa::path(
+        get,
+        path = "/v1/ws",
+        responses(
+            (status = 101, description = "websocket for subscriptions to the state of quotes and proofs (NUT-17)")
+        ),
+    )]
+#[instrument(name = "get_ws", skip_all)]
+pub async fn get_ws<DB: Database>(
+    ws: WebSocketUpgrade,
+    State(mint): State<Mint<DB>>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_socket(socket, mint))
+}
+
+struct Subscription {
+    kind: SubscriptionKind,
+    filters: HashSet<String>,
+}
+
+async fn handle_socket<DB: Database>(mut socket: WebSocket, mint: Mint<DB>) {
+    // the receiver is created before the first request, so no event is missed while the current state is loaded
+    let mut events = mint.subscribe_events();
+    let mut subscriptions = HashMap::<String, Subscription>::new();
+
+    loop {
+        let messages = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => handle_request(&mint, &mut subscriptions, &text).await,
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => {
+                    debug!("websocket closed: {err}");
+                    break;
+                }
+            },
+            event = events.recv() => match event {
+                Ok(payload) => notifications(&subscriptions, payload),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("websocket subscription lagged behind, {skipped} events are skipped");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        for message in messages {
+            if let Err(err) = socket.send(Message::Text(message)).await {
+                debug!("failed to send websocket message: {err}");
+                return;
+            }
+        }
+    }
+}
+
+/// Returns the response to the request followed by the notifications with the current state of a new subscription
+async fn handle_request<DB: Database>(
+    mint: &Mint<DB>,
+    subscriptions: &mut HashMap<String, Subscription>,
+    text: &str,
+) -> Vec<String> {
+    let request = match serde_json::from_str::<WsRequest>(text) {
+        Ok(request) => request,
+        Err(err) => {
+            return vec![to_json(&WsResponse::error(
+                PARSE_ERROR,
+                err.to_string(),
+                None,
+            ))]
+        }
+    };
+    if request.jsonrpc != JSONRPC_VERSION {
+        return vec![to_json(&WsResponse::error(
+            INVALID_REQUEST,
+            format!("unsupported jsonrpc version {}", request.jsonrpc),
+            Some(request.id),
+        ))];
+    }
+
+    match request.method {
+        WsMethod::Subscribe(params) => {
+            if subscriptions.contains_key(&params.sub_id) {
+                return vec![to_json(&WsResponse::error(
+                    INVALID_PARAMS,
+                    format!("subscription {} already exists", params.sub_id),
+                    Some(request.id),
+                ))];
+            }
+            let current = match current_state(mint, &params).await {
+                Ok(current) => current,
+                Err(err) => {
+                    return vec![to_json(&WsResponse::error(
+                        INVALID_PARAMS,
+                        err.to_string(),
+                        Some(request.id),
+                    ))]
+                }
+            };
+
+            let SubscribeParams {
+                kind,
+                sub_id,
+                filters,
+            } = params;
+            subscriptions.insert(
+                sub_id.clone(),
+                Subscription {
+                    kind,
+                    filters: filters.iter().map(|filter| filter.to_lowercase()).collect(),
+                },
+            );
+            std::iter::once(to_json(&WsResponse::ok(sub_id.clone(), request.id)))
+                .chain(
+                    current
+                        .into_iter()
+                        .map(|payload| to_json(&WsNotification::new(sub_id.clone(), payload))),
+                )
+                .collect()
+        }
+        WsMethod::Unsubscribe(UnsubscribeParams { sub_id }) => {
+            match subscriptions.remove(&sub_id) {
+                Some(_) => vec![to_json(&WsResponse::ok(sub_id, request.id))],
+                None => vec![to_json(&WsResponse::error(
+                    INVALID_PARAMS,
+                    format!("subscription {sub_id} not found"),
+                    Some(request.id),
+                ))],
+            }
+        }
+    }
+}
+
+/// Loads the current state of every filter of the subscription. Fails if a filter is invalid or unknown.
+async fn current_state<DB: Database>(
+    mint: &Mint<DB>,
+    params: &SubscribeParams,
+) -> Result<Vec<NotificationPayload>, MokshaMintError> {
+    if params.filters.is_empty() {
+        return Err(MokshaMintError::InvalidSubscription(
+            "no filters provided".to_owned(),
+        ));
+    }
+
+    match params.kind {
+        SubscriptionKind::Bolt11MintQuote => {
+            let mut current = vec![];
+            for filter in &params.filters {
+                let quote = mint.get_bolt11_mint_quote(&Uuid::from_str(filter)?).await?;
+                current.push(NotificationPayload::MintQuote(quote.into()));
+            }
+            Ok(current)
+        }
+        SubscriptionKind::Bolt11MeltQuote => {
+            let mut tx = mint.db.begin_tx().await?;
+            let mut current = vec![];
+            for filter in &params.filters {
+                let quote = mint
+                    .db
+                    .get_bolt11_melt_quote(&mut tx, &Uuid::from_str(filter)?)
+                    .await?;
+                current.push(NotificationPayload::MeltQuote(quote.into()));
+            }
+            tx.commit().await?;
+            Ok(current)
+        }
+        SubscriptionKind::ProofState => {
+            let ys = params
+                .filters
+                .iter()
+                .map(|filter| {
+                    PublicKey::from_str(filter).map_err(|_| {
+                        MokshaMintError::InvalidSubscription(format!("invalid Y {filter}"))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(mint
+                .check_state(&ys)
+                .await?
+                .into_iter()
+                .map(NotificationPayload::ProofState)
+                .collect())
+        }
+    }
+}
+
+/// Returns a notification for every subscription whose filters contain the quote or proof of the event
+fn notifications(
+    subscriptions: &HashMap<String, Subscription>,
+    payload: NotificationPayload,
+) -> Vec<String> {
+    let (kind, filter) = match &payload {
+        NotificationPayload::MintQuote(quote) => {
+            (SubscriptionKind::Bolt11MintQuote, quote.quote.clone())
+        }
+        NotificationPayload::MeltQuote(quote) => {
+            (SubscriptionKind::Bolt11MeltQuote, quote.quote.clone())
+        }
+        NotificationPayload::ProofState(ProofState { y, .. }) => {
+            (SubscriptionKind::ProofState, y.to_string())
+        }
+    };
+    subscriptions
+        .iter()
+        .filter(|(_, subscription)| {
+            subscription.kind == kind && subscription.filters.contains(&filter)
+        })
+        .map(|(sub_id, _)| to_json(&WsNotification::new(sub_id.clone(), payload.clone())))
+        .collect()
+}
+
+fn to_json<T: Serialize>(message: &T) -> String {
+    serde_json::to_string(message).expect("websocket message can't be serialized")
+}
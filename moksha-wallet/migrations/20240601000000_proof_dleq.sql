@@ -0,0 +1,2 @@
+-- DLEQ proof (NUT-12) of the mint including the blinding factor r
+ALTER TABLE proofs ADD COLUMN dleq TEXT CHECK (dleq IS NULL OR json_valid(dleq));
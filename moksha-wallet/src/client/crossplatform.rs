@@ -4,22 +4,27 @@ use moksha_core::{
     blind::BlindedMessage,
     keyset::Keysets,
     primitives::{
-        CurrencyUnit, KeysResponse, MintInfoResponse, PostMeltBolt11Request,
-        PostMeltBolt11Response, PostMeltBtcOnchainRequest, PostMeltBtcOnchainResponse,
-        PostMeltQuoteBolt11Request, PostMeltQuoteBolt11Response, PostMeltQuoteBtcOnchainRequest,
+        CurrencyUnit, KeysResponse, MintInfoResponse, PostCheckStateRequest,
+        PostCheckStateResponse, PostMeltBolt11Request, PostMeltBolt11Response,
+        PostMeltBtcOnchainRequest, PostMeltBtcOnchainResponse, PostMeltQuoteBolt11Request,
+        PostMeltQuoteBolt11Response, PostMeltQuoteBtcOnchainRequest,
         PostMeltQuoteBtcOnchainResponse, PostMintBolt11Request, PostMintBolt11Response,
         PostMintBtcOnchainRequest, PostMintBtcOnchainResponse, PostMintQuoteBolt11Request,
         PostMintQuoteBolt11Response, PostMintQuoteBtcOnchainRequest,
-        PostMintQuoteBtcOnchainResponse, PostSwapRequest, PostSwapResponse,
+        PostMintQuoteBtcOnchainResponse, PostRestoreRequest, PostRestoreResponse, PostSwapRequest,
+        PostSwapResponse,
     },
     proof::Proofs,
 };
+use secp256k1::PublicKey;
 
 use url::Url;
 
 use crate::{error::MokshaWalletError, http::CrossPlatformHttpClient};
 
 use super::CashuClient;
+#[cfg(not(target_arch = "wasm32"))]
+use {super::subscription::MintSubscription, moksha_core::subscription::SubscriptionKind};
 
 #[async_trait(?Send)]
 impl CashuClient for CrossPlatformHttpClient {
@@ -51,6 +56,26 @@ impl CashuClient for CrossPlatformHttpClient {
         self.do_post(&mint_url.join("v1/swap")?, &body).await
     }
 
+    async fn post_checkstate(
+        &self,
+        mint_url: &Url,
+        ys: Vec<PublicKey>,
+    ) -> Result<PostCheckStateResponse, MokshaWalletError> {
+        let body = PostCheckStateRequest { ys };
+
+        self.do_post(&mint_url.join("v1/checkstate")?, &body).await
+    }
+
+    async fn post_restore(
+        &self,
+        mint_url: &Url,
+        outputs: Vec<BlindedMessage>,
+    ) -> Result<PostRestoreResponse, MokshaWalletError> {
+        let body = PostRestoreRequest { outputs };
+
+        self.do_post(&mint_url.join("v1/restore")?, &body).await
+    }
+
     async fn post_melt_bolt11(
         &self,
         mint_url: &Url,
@@ -162,6 +187,16 @@ impl CashuClient for CrossPlatformHttpClient {
         self.do_get(&mint_url.join("v1/info")?).await
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn subscribe(
+        &self,
+        mint_url: &Url,
+        kind: SubscriptionKind,
+        filters: Vec<String>,
+    ) -> Result<MintSubscription, MokshaWalletError> {
+        MintSubscription::connect(mint_url, kind, filters).await
+    }
+
     async fn is_v1_supported(&self, mint_url: &Url) -> Result<bool, MokshaWalletError> {
         self.get_status(&mint_url.join("v1/info")?)
             .await
@@ -3,19 +3,26 @@ use moksha_core::{
     blind::BlindedMessage,
     keyset::Keysets,
     primitives::{
-        CurrencyUnit, KeysResponse, MintInfoResponse, PostMeltBolt11Response,
-        PostMeltBtcOnchainResponse, PostMeltQuoteBolt11Response, PostMeltQuoteBtcOnchainResponse,
-        PostMintBolt11Response, PostMintBtcOnchainResponse, PostMintQuoteBolt11Response,
-        PostMintQuoteBtcOnchainResponse, PostSwapResponse,
+        CurrencyUnit, KeysResponse, MintInfoResponse, PostCheckStateResponse,
+        PostMeltBolt11Response, PostMeltBtcOnchainResponse, PostMeltQuoteBolt11Response,
+        PostMeltQuoteBtcOnchainResponse, PostMintBolt11Response, PostMintBtcOnchainResponse,
+        PostMintQuoteBolt11Response, PostMintQuoteBtcOnchainResponse, PostRestoreResponse,
+        PostSwapResponse,
     },
     proof::Proofs,
 };
+use secp256k1::PublicKey;
 
 use url::Url;
 
 use crate::error::MokshaWalletError;
 
 pub mod crossplatform;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod subscription;
+
+#[cfg(not(target_arch = "wasm32"))]
+use {moksha_core::subscription::SubscriptionKind, subscription::MintSubscription};
 
 #[cfg(test)]
 use mockall::automock;
@@ -40,6 +47,18 @@ pub trait CashuClient {
         output: Vec<BlindedMessage>,
     ) -> Result<PostSwapResponse, MokshaWalletError>;
 
+    async fn post_checkstate(
+        &self,
+        mint_url: &Url,
+        ys: Vec<PublicKey>,
+    ) -> Result<PostCheckStateResponse, MokshaWalletError>;
+
+    async fn post_restore(
+        &self,
+        mint_url: &Url,
+        outputs: Vec<BlindedMessage>,
+    ) -> Result<PostRestoreResponse, MokshaWalletError>;
+
     async fn post_melt_bolt11(
         &self,
         mint_url: &Url,
@@ -83,6 +102,15 @@ pub trait CashuClient {
 
     async fn get_info(&self, mint_url: &Url) -> Result<MintInfoResponse, MokshaWalletError>;
 
+    /// Subscribes to the state of the given quotes or Ys of proofs via the websocket of the mint (NUT-17)
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn subscribe(
+        &self,
+        mint_url: &Url,
+        kind: SubscriptionKind,
+        filters: Vec<String>,
+    ) -> Result<MintSubscription, MokshaWalletError>;
+
     async fn is_v1_supported(&self, mint_url: &Url) -> Result<bool, MokshaWalletError>;
 
     async fn post_mint_onchain(
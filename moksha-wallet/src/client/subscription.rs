@@ -0,0 +1,114 @@
+use futures_util::{SinkExt, StreamExt};
+use moksha_core::subscription::{
+    NotificationPayload, SubscribeParams, SubscriptionKind, UnsubscribeParams, WsMessage, WsMethod,
+    WsRequest, WsResponse, WsResult,
+};
+use rand::Rng;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use url::Url;
+
+use crate::error::MokshaWalletError;
+
+/// A subscription to the state of mint quotes, melt quotes or proofs via the websocket of a mint (NUT-17)
+pub struct MintSubscription {
+    ws: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    sub_id: String,
+    next_request_id: u64,
+}
+
+impl MintSubscription {
+    /// Connects to the websocket of the mint and subscribes to the given quotes or Ys of proofs
+    pub async fn connect(
+        mint_url: &Url,
+        kind: SubscriptionKind,
+        filters: Vec<String>,
+    ) -> Result<Self, MokshaWalletError> {
+        let mut ws_url = mint_url.join("v1/ws")?;
+        let scheme = if ws_url.scheme() == "https" {
+            "wss"
+        } else {
+            "ws"
+        };
+        ws_url
+            .set_scheme(scheme)
+            .map_err(|_| MokshaWalletError::InvalidMintUrl(mint_url.to_string()))?;
+
+        let (ws, _) = tokio_tungstenite::connect_async(ws_url.as_str()).await?;
+        let mut subscription = Self {
+            ws,
+            sub_id: hex::encode(rand::thread_rng().gen::<[u8; 16]>()),
+            next_request_id: 0,
+        };
+        let sub_id = subscription.sub_id.clone();
+        subscription
+            .request(WsMethod::Subscribe(SubscribeParams {
+                kind,
+                sub_id,
+                filters,
+            }))
+            .await?;
+        Ok(subscription)
+    }
+
+    /// Waits for the next change of the state. The current state of every filter is returned right after subscribing.
+    pub async fn next(&mut self) -> Result<NotificationPayload, MokshaWalletError> {
+        loop {
+            if let WsMessage::Notification(notification) = self.next_message().await? {
+                if notification.params.sub_id == self.sub_id {
+                    return Ok(notification.params.payload);
+                }
+            }
+        }
+    }
+
+    /// Ends the subscription and closes the websocket
+    pub async fn unsubscribe(mut self) -> Result<(), MokshaWalletError> {
+        let sub_id = self.sub_id.clone();
+        self.request(WsMethod::Unsubscribe(UnsubscribeParams { sub_id }))
+            .await?;
+        self.ws.close(None).await?;
+        Ok(())
+    }
+
+    /// Sends the request and waits for its response. Notifications that are received in the meantime are dropped.
+    async fn request(&mut self, method: WsMethod) -> Result<(), MokshaWalletError> {
+        let id = self.next_request_id;
+        self.next_request_id += 1;
+        self.ws
+            .send(Message::Text(serde_json::to_string(&WsRequest::new(
+                method, id,
+            ))?))
+            .await?;
+
+        loop {
+            if let WsMessage::Response(WsResponse {
+                result,
+                id: Some(response_id),
+                ..
+            }) = self.next_message().await?
+            {
+                if response_id != id {
+                    continue;
+                }
+                return match result {
+                    WsResult::Result(_) => Ok(()),
+                    WsResult::Error(err) => Err(MokshaWalletError::MintError(err.message)),
+                };
+            }
+        }
+    }
+
+    async fn next_message(&mut self) -> Result<WsMessage, MokshaWalletError> {
+        loop {
+            match self.ws.next().await {
+                Some(Ok(Message::Text(text))) => return Ok(serde_json::from_str(&text)?),
+                Some(Ok(Message::Close(_))) | None => {
+                    return Err(MokshaWalletError::SubscriptionClosed)
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => return Err(err.into()),
+            }
+        }
+    }
+}
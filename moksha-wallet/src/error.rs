@@ -19,6 +19,14 @@ pub enum MokshaWalletError {
     #[cfg(not(target_arch = "wasm32"))]
     #[error("InvalidHeaderValueError - {0}")]
     InvalidHeaderValue(#[from] reqwest::header::InvalidHeaderValue),
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("WebSocketError - {0}")]
+    WebSocket(#[from] tokio_tungstenite::tungstenite::Error),
+
+    #[error("Subscription closed by the mint")]
+    SubscriptionClosed,
+
     #[error("{0}")]
     MintError(String),
 
@@ -61,6 +69,9 @@ pub enum MokshaWalletError {
     #[error("URLParseError - {0}")]
     Url(#[from] url::ParseError),
 
+    #[error("Invalid mint url {0}")]
+    InvalidMintUrl(String),
+
     #[error("Unsupported version: Only mints with /v1 api are supported")]
     UnsupportedApiVersion,
 
@@ -79,6 +90,9 @@ pub enum MokshaWalletError {
     #[error("Found multiple seeds in the database. This is not supported.")]
     MultipleSeeds,
 
+    #[error("The seed words don't match the seed of the wallet, which already has tokens or used secrets")]
+    SeedMismatch,
+
     #[error("Not valid hex string")]
     Hex(#[from] hex::FromHexError),
 
@@ -87,4 +101,7 @@ pub enum MokshaWalletError {
 
     #[error("Pubkey not found")]
     PubkeyNotFound,
+
+    #[error("Invalid DLEQ proof for signature {0}")]
+    InvalidDleq(String),
 }
@@ -24,6 +24,8 @@ pub struct WalletKeyset {
     pub last_index: u64,
     pub public_keys: HashMap<u64, PublicKey>,
     pub active: bool,
+    /// fee in parts per thousand units for every input of this keyset
+    pub input_fee_ppk: u64,
 }
 
 impl WalletKeysetFilter for Vec<WalletKeyset> {
@@ -45,6 +47,7 @@ impl WalletKeyset {
         last_index: u64,
         public_keys: HashMap<u64, PublicKey>,
         active: bool,
+        input_fee_ppk: u64,
     ) -> Self {
         Self {
             id: None,
@@ -54,6 +57,7 @@ impl WalletKeyset {
             last_index,
             public_keys,
             active,
+            input_fee_ppk,
         }
     }
 }
@@ -100,6 +104,12 @@ pub trait LocalStore {
         seed_words: &str,
     ) -> Result<(), MokshaWalletError>;
 
+    async fn update_seed(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        seed_words: &str,
+    ) -> Result<(), MokshaWalletError>;
+
     async fn get_seed(
         &self,
         tx: &mut sqlx::Transaction<Self::DB>,
@@ -158,6 +168,12 @@ pub trait LocalStore {
         seed_words: &str,
     ) -> Result<(), MokshaWalletError>;
 
+    async fn update_seed(
+        &self,
+        _tx: &mut RexieTransaction,
+        seed_words: &str,
+    ) -> Result<(), MokshaWalletError>;
+
     async fn get_seed(
         &self,
         _tx: &mut RexieTransaction,
@@ -141,6 +141,14 @@ impl LocalStore for RexieLocalStore {
         todo!()
     }
 
+    async fn update_seed(
+        &self,
+        _tx: &mut RexieTransaction,
+        _seed_words: &str,
+    ) -> std::result::Result<(), MokshaWalletError> {
+        todo!()
+    }
+
     async fn get_seed(
         &self,
         _tx: &mut RexieTransaction,
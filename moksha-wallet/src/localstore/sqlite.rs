@@ -60,9 +60,13 @@ impl LocalStore for SqliteLocalStore {
         for proof in proofs.proofs() {
             let c = proof.c.to_string();
             let amount = proof.amount as i64;
+            let dleq = proof
+                .dleq
+                .map(|dleq| serde_json::to_string(&dleq))
+                .transpose()?;
             sqlx::query!(
-                "INSERT INTO proofs (keyset_id, amount, C, secret, time_created) VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP);",
-            proof.keyset_id,amount, c, proof.secret )
+                "INSERT INTO proofs (keyset_id, amount, C, secret, dleq, time_created) VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP);",
+            proof.keyset_id,amount, c, proof.secret, dleq )
             .execute(&mut **tx)
             .await?;
         }
@@ -73,7 +77,7 @@ impl LocalStore for SqliteLocalStore {
         &self,
         tx: &mut sqlx::Transaction<Self::DB>,
     ) -> Result<Proofs, MokshaWalletError> {
-        let rows = sqlx::query!("SELECT keyset_id, amount, C, secret FROM proofs;")
+        let rows = sqlx::query!("SELECT keyset_id, amount, C, secret, dleq FROM proofs;")
             .fetch_all(&mut **tx)
             .await?;
 
@@ -86,6 +90,10 @@ impl LocalStore for SqliteLocalStore {
                 c: row.C.parse().expect("Invalid Pubkey"),
                 secret: row.secret,
                 script: None,
+                dleq: row
+                    .dleq
+                    .map(|dleq| serde_json::from_str(&dleq).expect("invalid json in localstore")),
+                witness: None,
             })
             .collect::<Vec<Proof>>()
             .into())
@@ -101,10 +109,11 @@ impl LocalStore for SqliteLocalStore {
         let currency_unit = keyset.currency_unit.to_string();
         let last_index = keyset.last_index as i64;
         let public_keys = serde_json::to_string(&keyset.public_keys)?;
+        let input_fee_ppk = keyset.input_fee_ppk as i64;
         sqlx::query!(
-            r#"INSERT INTO keysets (keyset_id, mint_url, currency_unit, last_index, public_keys, active) VALUES ($1, $2, $3, $4, $5, $6)
-            ON CONFLICT(keyset_id, mint_url) DO UPDATE SET currency_unit = $3, public_keys = $5, active = $6;
-            "#,keyset_id, mint_url, currency_unit, last_index, public_keys, keyset.active)
+            r#"INSERT INTO keysets (keyset_id, mint_url, currency_unit, last_index, public_keys, active, input_fee_ppk) VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT(keyset_id, mint_url) DO UPDATE SET currency_unit = $3, public_keys = $5, active = $6, input_fee_ppk = $7;
+            "#,keyset_id, mint_url, currency_unit, last_index, public_keys, keyset.active, input_fee_ppk)
         .execute(&mut **tx)
         .await?;
         Ok(())
@@ -114,7 +123,7 @@ impl LocalStore for SqliteLocalStore {
         &self,
         tx: &mut sqlx::Transaction<Self::DB>,
     ) -> Result<Vec<WalletKeyset>, MokshaWalletError> {
-        let rows = sqlx::query!("SELECT id, mint_url, keyset_id, currency_unit, active, last_index, public_keys FROM keysets;")
+        let rows = sqlx::query!("SELECT id, mint_url, keyset_id, currency_unit, active, last_index, public_keys, input_fee_ppk FROM keysets;")
             .fetch_all(&mut **tx)
             .await?;
 
@@ -139,6 +148,7 @@ impl LocalStore for SqliteLocalStore {
                     active,
                     last_index: last_index as u64,
                     public_keys,
+                    input_fee_ppk: row.input_fee_ppk as u64,
                 })
             })
             .collect::<Result<Vec<WalletKeyset>, SqliteError>>()?)
@@ -176,6 +186,20 @@ impl LocalStore for SqliteLocalStore {
         Ok(())
     }
 
+    async fn update_seed(
+        &self,
+        tx: &mut sqlx::Transaction<Self::DB>,
+        seed_words: &str,
+    ) -> Result<(), MokshaWalletError> {
+        sqlx::query!(
+            "INSERT INTO seed (id, seed_words) VALUES (1, $1) ON CONFLICT(id) DO UPDATE SET seed_words = $1;",
+            seed_words
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
     async fn get_seed(
         &self,
         tx: &mut sqlx::Transaction<Self::DB>,
@@ -220,7 +244,12 @@ impl SqliteLocalStore {
 mod tests {
     use super::SqliteLocalStore;
     use crate::localstore::LocalStore;
-    use moksha_core::{fixture::read_fixture, token::TokenV3};
+    use moksha_core::{
+        fixture::read_fixture,
+        proof::{ProofDleq, Proofs},
+        token::TokenV3,
+    };
+    use secp256k1::SecretKey;
 
     #[tokio::test]
     async fn test_add_proofs() -> anyhow::Result<()> {
@@ -239,6 +268,37 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_add_proofs_with_dleq() -> anyhow::Result<()> {
+        let db = SqliteLocalStore::with_in_memory().await?;
+        let mut tx = db.begin_tx().await?;
+        let tokens: TokenV3 = read_fixture("token_60.cashu")?
+            .trim()
+            .to_string()
+            .try_into()?;
+        let key = SecretKey::from_slice(&[1u8; 32])?;
+        let proofs: Proofs = tokens
+            .proofs()
+            .proofs()
+            .into_iter()
+            .map(|proof| {
+                proof.with_dleq(ProofDleq {
+                    e: key,
+                    s: key,
+                    r: key,
+                })
+            })
+            .collect::<Vec<_>>()
+            .into();
+
+        db.add_proofs(&mut tx, &proofs).await?;
+
+        let loaded_proofs = db.get_proofs(&mut tx).await?;
+        assert_eq!(proofs, loaded_proofs);
+        tx.commit().await?;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_delete_proofs() -> anyhow::Result<()> {
         let localstore = SqliteLocalStore::with_in_memory().await?;
@@ -3,17 +3,22 @@ use moksha_core::{
     blind::{BlindedMessage, BlindedSignature, BlindingFactor, TotalAmount},
     dhke::Dhke,
     keyset::KeysetId,
+    p2pk::SigFlag,
     primitives::{
         CurrencyUnit, MeltBtcOnchainState, MintBtcOnchainState, MintInfoResponse, PaymentMethod,
         PostMeltBolt11Response, PostMeltBtcOnchainResponse, PostMeltQuoteBolt11Response,
         PostMeltQuoteBtcOnchainResponse, PostMintQuoteBolt11Response,
-        PostMintQuoteBtcOnchainResponse,
+        PostMintQuoteBtcOnchainResponse, ProofState, SpendState,
     },
-    proof::{Proof, Proofs},
+    proof::{Proof, ProofDleq, Proofs},
+    secret::SpendingConditions,
     token::TokenV3,
 };
 
-use secp256k1::PublicKey;
+#[cfg(not(target_arch = "wasm32"))]
+use moksha_core::subscription::{NotificationPayload, SubscriptionKind};
+
+use secp256k1::{PublicKey, SecretKey};
 use url::Url;
 
 use crate::{
@@ -30,6 +35,12 @@ use std::{
     vec,
 };
 
+/// number of secrets that are sent to the mint in one restore request
+const RESTORE_BATCH_SIZE: u32 = 100;
+
+/// restoring a keyset stops after this many consecutive batches without any known signature
+const RESTORE_GAP_LIMIT: u32 = 3;
+
 #[derive(Clone)]
 pub struct Wallet<L, C>
 where
@@ -172,6 +183,28 @@ where
         })
     }
 
+    /// Waits until the bolt11 mint quote is paid. The state of the quote is pushed by the mint via a websocket
+    /// subscription (NUT-17), so the mint has to support subscriptions for bolt11 mint quotes.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn wait_for_mint_quote_bolt11_paid(
+        &self,
+        mint_url: &Url,
+        quote: String,
+    ) -> Result<(), MokshaWalletError> {
+        let mut subscription = self
+            .client
+            .subscribe(mint_url, SubscriptionKind::Bolt11MintQuote, vec![quote])
+            .await?;
+        loop {
+            if let NotificationPayload::MintQuote(PostMintQuoteBolt11Response {
+                paid: true, ..
+            }) = subscription.next().await?
+            {
+                return subscription.unsubscribe().await;
+            }
+        }
+    }
+
     pub async fn is_onchain_paid(
         &self,
         mint_url: &Url,
@@ -248,6 +281,7 @@ where
                 0,
                 public_keys,
                 keyset.active,
+                keyset.input_fee_ppk,
             );
 
             result.push(wallet_keyset.clone());
@@ -270,6 +304,27 @@ where
         &self,
         wallet_keyset: &WalletKeyset,
         amount: u64,
+    ) -> Result<TokenV3, MokshaWalletError> {
+        self.send_tokens_with_conditions(wallet_keyset, amount, None)
+            .await
+    }
+
+    /// Sends tokens that are locked to the given spending conditions, i.e. a pubkey (NUT-11) or the hash of a preimage (NUT-14).
+    pub async fn send_locked_tokens(
+        &self,
+        wallet_keyset: &WalletKeyset,
+        amount: u64,
+        conditions: &SpendingConditions,
+    ) -> Result<TokenV3, MokshaWalletError> {
+        self.send_tokens_with_conditions(wallet_keyset, amount, Some(conditions))
+            .await
+    }
+
+    async fn send_tokens_with_conditions(
+        &self,
+        wallet_keyset: &WalletKeyset,
+        amount: u64,
+        conditions: Option<&SpendingConditions>,
     ) -> Result<TokenV3, MokshaWalletError> {
         let balance = self.get_balance().await?;
         if amount > balance {
@@ -284,11 +339,24 @@ where
             .proofs_by_keyset(&wallet_keyset.keyset_id);
         tx.commit().await?;
 
-        let selected_proofs = all_proofs.proofs_for_amount(amount)?;
+        let input_fees = self.get_input_fees().await?;
+        let selected_proofs = all_proofs.proofs_for_amount_with_fee(amount, |proof| {
+            input_fees
+                .get(&proof.keyset_id)
+                .copied()
+                .unwrap_or_default()
+        })?;
         let selected_tokens = (wallet_keyset.mint_url.to_owned(), selected_proofs.clone()).into();
 
         let (remaining_tokens, result) = self
-            .swap_tokens(wallet_keyset, &selected_tokens, amount.into())
+            .swap_tokens_with_conditions(
+                wallet_keyset,
+                &selected_tokens,
+                amount.into(),
+                conditions,
+                None,
+                None,
+            )
             .await?;
 
         let mut tx = self.localstore.begin_tx().await?;
@@ -308,9 +376,56 @@ where
         wallet_keyset: &WalletKeyset,
         tokens: &TokenV3,
     ) -> Result<(), MokshaWalletError> {
-        let total_amount = tokens.total_amount();
+        self.receive_tokens_with_witness(wallet_keyset, tokens, None, None)
+            .await
+    }
+
+    /// Receives tokens that are locked to the pubkey of the given secret key (NUT-11). The locked proofs are signed before they are swapped.
+    pub async fn receive_locked_tokens(
+        &self,
+        wallet_keyset: &WalletKeyset,
+        tokens: &TokenV3,
+        signing_key: &SecretKey,
+    ) -> Result<(), MokshaWalletError> {
+        self.receive_tokens_with_witness(wallet_keyset, tokens, Some(signing_key), None)
+            .await
+    }
+
+    /// Receives tokens that are locked to the hash of the given hex encoded preimage (NUT-14).
+    /// The signing key is only required if the tokens are additionally locked to a pubkey.
+    pub async fn receive_htlc_tokens(
+        &self,
+        wallet_keyset: &WalletKeyset,
+        tokens: &TokenV3,
+        preimage: &str,
+        signing_key: Option<&SecretKey>,
+    ) -> Result<(), MokshaWalletError> {
+        self.receive_tokens_with_witness(wallet_keyset, tokens, signing_key, Some(preimage))
+            .await
+    }
+
+    async fn receive_tokens_with_witness(
+        &self,
+        wallet_keyset: &WalletKeyset,
+        tokens: &TokenV3,
+        signing_key: Option<&SecretKey>,
+        preimage: Option<&str>,
+    ) -> Result<(), MokshaWalletError> {
+        self.verify_proofs_dleq(wallet_keyset, &tokens.proofs())?;
+
+        let amount = tokens
+            .total_amount()
+            .checked_sub(self.input_fee(&tokens.proofs()).await?)
+            .ok_or(MokshaWalletError::NotEnoughTokens)?;
         let (_, redeemed_tokens) = self
-            .swap_tokens(wallet_keyset, tokens, total_amount.into())
+            .swap_tokens_with_conditions(
+                wallet_keyset,
+                tokens,
+                amount.into(),
+                None,
+                signing_key,
+                preimage,
+            )
             .await?;
         let mut tx = self.localstore.begin_tx().await?;
         self.localstore
@@ -320,6 +435,209 @@ where
         Ok(())
     }
 
+    /// Asks the mint for the state of all proofs of the keyset (NUT-07) and removes the spent proofs from the localstore
+    pub async fn check_proofs_state(
+        &self,
+        wallet_keyset: &WalletKeyset,
+    ) -> Result<Vec<ProofState>, MokshaWalletError> {
+        let mut tx = self.localstore.begin_tx().await?;
+        let proofs = self
+            .localstore
+            .get_proofs(&mut tx)
+            .await?
+            .proofs_by_keyset(&wallet_keyset.keyset_id);
+        tx.commit().await?;
+
+        if proofs.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let ys = proofs
+            .proofs()
+            .iter()
+            .map(|proof| Dhke::hash_to_curve(proof.secret.as_bytes()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let states = self
+            .client
+            .post_checkstate(&wallet_keyset.mint_url, ys.clone())
+            .await?
+            .states;
+
+        let spent_ys = states
+            .iter()
+            .filter(|state| state.state == SpendState::Spent)
+            .map(|state| state.y)
+            .collect::<Vec<_>>();
+        let spent_proofs: Proofs = proofs
+            .proofs()
+            .into_iter()
+            .zip(ys)
+            .filter(|(_, y)| spent_ys.contains(y))
+            .map(|(proof, _)| proof)
+            .collect::<Vec<_>>()
+            .into();
+
+        if !spent_proofs.is_empty() {
+            let mut tx = self.localstore.begin_tx().await?;
+            self.localstore
+                .delete_proofs(&mut tx, &spent_proofs)
+                .await?;
+            tx.commit().await?;
+        }
+        Ok(states)
+    }
+
+    /// Restores the tokens of all known keysets from the given seed words (NUT-13).
+    ///
+    /// For every keyset batches of deterministic secrets are sent to the mint until [`RESTORE_GAP_LIMIT`] batches
+    /// in a row are unknown to the mint. Only unspent proofs are added to the localstore. The seed words replace
+    /// the seed of the wallet once all keysets are restored. A wallet that already has tokens or used secrets of
+    /// another seed is only restored with `overwrite_seed`. Returns the restored amount.
+    pub async fn restore(
+        &mut self,
+        seed_words: &str,
+        overwrite_seed: bool,
+    ) -> Result<u64, MokshaWalletError> {
+        let secret = DeterministicSecret::from_seed_words(seed_words)?;
+
+        let mut tx = self.localstore.begin_tx().await?;
+        let stored_seed = self.localstore.get_seed(&mut tx).await?;
+        let keysets = self.localstore.get_keysets(&mut tx).await?;
+        let is_fresh = self.localstore.get_proofs(&mut tx).await?.is_empty()
+            && keysets.iter().all(|keyset| keyset.last_index == 0);
+        tx.commit().await?;
+        if stored_seed.as_deref() != Some(seed_words) && !is_fresh && !overwrite_seed {
+            return Err(MokshaWalletError::SeedMismatch);
+        }
+
+        let mut restored = vec![];
+        for keyset in keysets {
+            let (proofs, last_used_index) = self.restore_keyset(&secret, &keyset).await?;
+            restored.push((keyset, proofs, last_used_index));
+        }
+
+        let mut tx = self.localstore.begin_tx().await?;
+        let known_secrets = self
+            .localstore
+            .get_proofs(&mut tx)
+            .await?
+            .proofs()
+            .into_iter()
+            .map(|proof| proof.secret)
+            .collect::<HashSet<_>>();
+        let mut restored_amount = 0;
+        for (keyset, proofs, last_used_index) in restored {
+            let Some(last_used_index) = last_used_index else {
+                continue;
+            };
+            let new_proofs: Proofs = proofs
+                .proofs()
+                .into_iter()
+                .filter(|proof| !known_secrets.contains(&proof.secret))
+                .collect::<Vec<_>>()
+                .into();
+            restored_amount += new_proofs.total_amount();
+            self.localstore.add_proofs(&mut tx, &new_proofs).await?;
+            self.localstore
+                .update_keyset_last_index(
+                    &mut tx,
+                    &WalletKeyset {
+                        last_index: keyset.last_index.max(last_used_index as u64),
+                        ..keyset
+                    },
+                )
+                .await?;
+        }
+        if stored_seed.as_deref() != Some(seed_words) {
+            self.localstore.update_seed(&mut tx, seed_words).await?;
+        }
+        tx.commit().await?;
+        self.secret = secret;
+        Ok(restored_amount)
+    }
+
+    /// Returns the unspent proofs of the keyset that the mint signed for the seed and the last used index
+    async fn restore_keyset(
+        &self,
+        secret: &DeterministicSecret,
+        keyset: &WalletKeyset,
+    ) -> Result<(Proofs, Option<u32>), MokshaWalletError> {
+        let mut start_index = 0;
+        let mut empty_batches = 0;
+        let mut last_used_index = None;
+        let mut restored_proofs = vec![];
+
+        while empty_batches < RESTORE_GAP_LIMIT {
+            let secrets =
+                secret.derive_range(&keyset.keyset_id, start_index, RESTORE_BATCH_SIZE)?;
+            let outputs = secrets
+                .iter()
+                .map(|(secret, blinding_factor)| {
+                    Ok(BlindedMessage {
+                        amount: 1,
+                        b_: self.dhke.step1_alice(secret.clone(), blinding_factor)?,
+                        id: keyset.keyset_id.to_string(),
+                        witness: None,
+                    })
+                })
+                .collect::<Result<Vec<_>, MokshaWalletError>>()?;
+
+            let response = self
+                .client
+                .post_restore(&keyset.mint_url, outputs.clone())
+                .await?;
+
+            if response.signatures.is_empty() {
+                empty_batches += 1;
+            } else {
+                empty_batches = 0;
+            }
+
+            for (output, signature) in response.outputs.into_iter().zip(response.signatures) {
+                let Some(index) = outputs.iter().position(|o| o.b_ == output.b_) else {
+                    continue;
+                };
+                let (secret, blinding_factor) = secrets[index].clone();
+                let proofs = self.create_proofs_from_blinded_signatures(
+                    &keyset.keyset_id,
+                    &keyset.public_keys,
+                    vec![signature],
+                    vec![secret],
+                    vec![(output, blinding_factor)],
+                )?;
+                restored_proofs.extend(proofs.proofs());
+                last_used_index = last_used_index.max(Some(start_index + index as u32));
+            }
+            start_index += RESTORE_BATCH_SIZE;
+        }
+
+        if last_used_index.is_none() {
+            return Ok((Proofs::empty(), None));
+        }
+
+        let ys = restored_proofs
+            .iter()
+            .map(|proof| Dhke::hash_to_curve(proof.secret.as_bytes()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let unspent_ys = self
+            .client
+            .post_checkstate(&keyset.mint_url, ys.clone())
+            .await?
+            .states
+            .into_iter()
+            .filter(|state| state.state == SpendState::Unspent)
+            .map(|state| state.y)
+            .collect::<HashSet<_>>();
+        let unspent_proofs: Proofs = restored_proofs
+            .into_iter()
+            .zip(ys)
+            .filter(|(_, y)| unspent_ys.contains(y))
+            .map(|(proof, _)| proof)
+            .collect::<Vec<_>>()
+            .into();
+        Ok((unspent_proofs, last_used_index))
+    }
+
     pub async fn get_mint_quote(
         &self,
         mint_url: &Url,
@@ -352,12 +670,22 @@ where
         let all_proofs = self.localstore.get_proofs(&mut tx).await?;
         tx.commit().await?;
 
-        let ln_amount = Self::get_invoice_amount(&invoice)? + melt_quote.fee_reserve;
+        // the proofs for the melt are swapped first, so they have to cover their own input fee as well
+        let ln_amount = Self::amount_with_input_fee(
+            wallet_keyset,
+            Self::get_invoice_amount(&invoice)? + melt_quote.fee_reserve,
+        );
 
         if ln_amount > all_proofs.total_amount() {
             return Err(MokshaWalletError::NotEnoughTokens);
         }
-        let selected_proofs = all_proofs.proofs_for_amount(ln_amount)?;
+        let input_fees = self.get_input_fees().await?;
+        let selected_proofs = all_proofs.proofs_for_amount_with_fee(ln_amount, |proof| {
+            input_fees
+                .get(&proof.keyset_id)
+                .copied()
+                .unwrap_or_default()
+        })?;
 
         let total_proofs = {
             let selected_tokens =
@@ -451,12 +779,19 @@ where
         let all_proofs = self.localstore.get_proofs(&mut tx).await?;
         tx.commit().await?;
 
-        let ln_amount = melt_quote.amount + melt_quote.fee;
+        let ln_amount =
+            Self::amount_with_input_fee(wallet_keyset, melt_quote.amount + melt_quote.fee);
 
         if ln_amount > all_proofs.total_amount() {
             return Err(MokshaWalletError::NotEnoughTokens);
         }
-        let selected_proofs = all_proofs.proofs_for_amount(ln_amount)?;
+        let input_fees = self.get_input_fees().await?;
+        let selected_proofs = all_proofs.proofs_for_amount_with_fee(ln_amount, |proof| {
+            input_fees
+                .get(&proof.keyset_id)
+                .copied()
+                .unwrap_or_default()
+        })?;
 
         let mut tx = self.localstore.begin_tx().await?;
         let total_proofs = {
@@ -521,14 +856,66 @@ where
         Ok(secret_range)
     }
 
+    /// Returns the `input_fee_ppk` of all known keysets by keyset id
+    async fn get_input_fees(&self) -> Result<HashMap<String, u64>, MokshaWalletError> {
+        Ok(self
+            .get_wallet_keysets()
+            .await?
+            .into_iter()
+            .map(|keyset| (keyset.keyset_id.to_string(), keyset.input_fee_ppk))
+            .collect())
+    }
+
+    /// Returns the fee the mint charges for spending the proofs (NUT-02). Proofs of unknown keysets are free.
+    async fn input_fee(&self, proofs: &Proofs) -> Result<u64, MokshaWalletError> {
+        let input_fees = self.get_input_fees().await?;
+        Ok(proofs.input_fee(|proof| {
+            input_fees
+                .get(&proof.keyset_id)
+                .copied()
+                .unwrap_or_default()
+        }))
+    }
+
+    /// Returns the amount of new proofs of the keyset that is needed to spend `amount` after paying the input fee of these proofs
+    fn amount_with_input_fee(wallet_keyset: &WalletKeyset, amount: u64) -> u64 {
+        let fee = |total: u64| {
+            (Amount(total).split().len() as u64 * wallet_keyset.input_fee_ppk).div_ceil(1_000)
+        };
+        let mut total = amount;
+        while total < amount + fee(total) {
+            total += 1;
+        }
+        total
+    }
+
     pub async fn swap_tokens(
         &self,
         wallet_keyset: &WalletKeyset,
         tokens: &TokenV3,
         splt_amount: Amount,
+    ) -> Result<(TokenV3, TokenV3), MokshaWalletError> {
+        self.swap_tokens_with_conditions(wallet_keyset, tokens, splt_amount, None, None, None)
+            .await
+    }
+
+    /// Swaps the tokens like `swap_tokens`. If conditions are given, the second tokens are locked to them (NUT-10).
+    /// Locked inputs are unlocked with the given signing key and preimage, see `unlock_proofs`.
+    async fn swap_tokens_with_conditions(
+        &self,
+        wallet_keyset: &WalletKeyset,
+        tokens: &TokenV3,
+        splt_amount: Amount,
+        conditions: Option<&SpendingConditions>,
+        signing_key: Option<&SecretKey>,
+        preimage: Option<&str>,
     ) -> Result<(TokenV3, TokenV3), MokshaWalletError> {
         let total_token_amount = tokens.total_amount();
-        let first_amount: Amount = (total_token_amount - splt_amount.0).into();
+        let fee = self.input_fee(&tokens.proofs()).await?;
+        let first_amount: Amount = total_token_amount
+            .checked_sub(splt_amount.0 + fee)
+            .ok_or(MokshaWalletError::NotEnoughTokens)?
+            .into();
         let first_secrets = self
             .create_secrets(&wallet_keyset.keyset_id, first_amount.split().len() as u32)
             .await?;
@@ -541,9 +928,23 @@ where
         // ############################################################################
 
         let second_amount = splt_amount.clone();
-        let second_secrets = self
-            .create_secrets(&wallet_keyset.keyset_id, second_amount.split().len() as u32)
-            .await?;
+        let second_secrets = match conditions {
+            // locked secrets can't be derived from the seed, so they use random blinding factors
+            Some(conditions) => second_amount
+                .split()
+                .into_iter()
+                .map(|_| {
+                    (
+                        conditions.to_secret().to_string(),
+                        SecretKey::new(&mut secp256k1::rand::thread_rng()).into(),
+                    )
+                })
+                .collect(),
+            None => {
+                self.create_secrets(&wallet_keyset.keyset_id, second_amount.split().len() as u32)
+                    .await?
+            }
+        };
         let second_outputs = self.create_blinded_messages(
             &wallet_keyset.keyset_id,
             second_amount,
@@ -554,13 +955,20 @@ where
         total_outputs.extend(get_blinded_msg(first_outputs.clone()));
         total_outputs.extend(get_blinded_msg(second_outputs.clone()));
 
-        if tokens.total_amount() != total_outputs.total_amount() {
+        if tokens.total_amount() != total_outputs.total_amount() + fee {
             return Err(MokshaWalletError::InvalidProofs);
         }
 
+        let (inputs, sig_all) = Self::unlock_proofs(tokens.proofs(), signing_key, preimage)?;
+        if let (true, Some(signing_key)) = (sig_all, signing_key) {
+            for output in total_outputs.iter_mut() {
+                output.sign_p2pk(signing_key)?;
+            }
+        }
+
         let split_result = self
             .client
-            .post_swap(&wallet_keyset.mint_url, tokens.proofs(), total_outputs)
+            .post_swap(&wallet_keyset.mint_url, inputs, total_outputs)
             .await?;
 
         if split_result.signatures.is_empty() {
@@ -596,18 +1004,49 @@ where
         )
             .into();
 
-        if tokens.total_amount() != first_tokens.total_amount() + second_tokens.total_amount() {
-            println!(
-                "Error in swap: input {:?} != output {:?} + {:?}",
+        if tokens.total_amount() != first_tokens.total_amount() + second_tokens.total_amount() + fee
+        {
+            return Err(MokshaWalletError::UnexpectedResponse(format!(
+                "swap of {} returned {} + {} with a fee of {}",
                 tokens.total_amount(),
                 first_tokens.total_amount(),
-                second_tokens.total_amount()
-            );
+                second_tokens.total_amount(),
+                fee
+            )));
         }
 
         Ok((first_tokens, second_tokens))
     }
 
+    /// Adds the witness to all locked proofs. P2PK locked proofs are signed with the signing key and HTLC locked proofs get the preimage
+    /// and a signature if a signing key is given. Returns true if any of the proofs requires `SIG_ALL`.
+    fn unlock_proofs(
+        proofs: Proofs,
+        signing_key: Option<&SecretKey>,
+        preimage: Option<&str>,
+    ) -> Result<(Proofs, bool), MokshaWalletError> {
+        let mut sig_all = false;
+        let proofs = proofs
+            .proofs()
+            .into_iter()
+            .map(|mut proof| {
+                match (proof.spending_conditions()?, signing_key, preimage) {
+                    (Some(SpendingConditions::P2PK(conditions)), Some(signing_key), _) => {
+                        sig_all |= conditions.sig_flag == SigFlag::SigAll;
+                        proof.sign_p2pk(signing_key)?;
+                    }
+                    (Some(SpendingConditions::HTLC(_)), Some(_), _)
+                    | (Some(SpendingConditions::HTLC(_)), _, Some(_)) => {
+                        proof.add_htlc_witness(preimage.unwrap_or_default(), signing_key);
+                    }
+                    _ => {}
+                }
+                Ok(proof)
+            })
+            .collect::<Result<Vec<_>, MokshaWalletError>>()?;
+        Ok((proofs.into(), sig_all))
+    }
+
     pub async fn get_mint_info(
         &self,
         mint_url: &Url,
@@ -671,6 +1110,7 @@ where
                         amount,
                         b_,
                         id: wallet_keyset.keyset_id.to_string(), // FIXME use keyset_id
+                        witness: None,
                     },
                     blinding_factor,
                     secret,
@@ -712,21 +1152,17 @@ where
         };
 
         // step 3: unblind signatures
-        let current_keyset_id = wallet_keyset.keyset_id.to_string(); // FIXME
-
-        let proofs = signatures
-            .iter()
-            .zip(blinded_messages)
-            .map(|(p, (_, priv_key, secret))| {
-                let key = wallet_keyset
-                    .public_keys
-                    .get(&p.amount)
-                    .expect("msg amount not found in mint keys");
-                let pub_alice = self.dhke.step3_alice(p.c_, priv_key, *key).unwrap();
-                Proof::new(p.amount, secret, pub_alice, current_keyset_id.clone())
-            })
-            .collect::<Vec<Proof>>()
-            .into();
+        let (outputs, secrets) = blinded_messages
+            .into_iter()
+            .map(|(msg, blinding_factor, secret)| ((msg, blinding_factor), secret))
+            .unzip();
+        let proofs = self.create_proofs_from_blinded_signatures(
+            &wallet_keyset.keyset_id,
+            &wallet_keyset.public_keys,
+            signatures,
+            secrets,
+            outputs,
+        )?;
 
         let tokens: TokenV3 = (wallet_keyset.mint_url.to_owned(), proofs).into();
         let mut tx = self.localstore.begin_tx().await?;
@@ -747,10 +1183,10 @@ where
             return Ok(vec![]);
         }
 
-        let fee_reserve_float = fee_reserve.0 as f64;
-        let count = (fee_reserve_float.log2().ceil() as u64).max(1);
+        // ceil(log2(fee_reserve)) outputs are enough for any amount up to the fee reserve (NUT-08)
+        let count = (u64::BITS - (fee_reserve.0 - 1).leading_zeros()).max(1);
 
-        let secret_range = self.create_secrets(keyset_id, count as u32).await?;
+        let secret_range = self.create_secrets(keyset_id, count).await?;
         let blinded_messages = secret_range
             .into_iter()
             .map(|(secret, blinding_factor)| {
@@ -760,6 +1196,7 @@ where
                         amount: 1,
                         b_,
                         id: keyset_id.to_string(),
+                        witness: None,
                     },
                     blinding_factor,
                     secret,
@@ -788,6 +1225,7 @@ where
                         amount,
                         b_,
                         id: keyset_id.to_string(),
+                        witness: None,
                     },
                     blinding_factor,
                 ))
@@ -805,33 +1243,62 @@ where
     ) -> Result<Proofs, MokshaWalletError> {
         let current_keyset_id = keyset_id.to_string(); // FIXME
 
-        let blinding_factors = outputs
-            .into_iter()
-            .map(|(_, secret)| secret)
-            .collect::<Vec<BlindingFactor>>();
-
         Ok(signatures
             .iter()
-            .zip(blinding_factors)
+            .zip(outputs)
             .zip(secrets)
-            .map(|((p, blinding_factor), secret)| {
+            .map(|((p, (blinded_message, blinding_factor)), secret)| {
                 let key = pub_keys
                     .get(&p.amount)
                     .ok_or(MokshaWalletError::PubkeyNotFound)?;
-                let pub_alice = self
-                    .dhke
-                    .step3_alice(p.c_, blinding_factor.to_owned(), *key)?;
-                Ok(Proof::new(
-                    p.amount,
-                    secret,
-                    pub_alice,
-                    current_keyset_id.clone(),
-                ))
+
+                if let Some(dleq) = &p.dleq {
+                    if !self
+                        .dhke
+                        .verify_dleq(dleq, *key, blinded_message.b_, p.c_)?
+                    {
+                        return Err(MokshaWalletError::InvalidDleq(p.c_.to_string()));
+                    }
+                }
+
+                let pub_alice = self.dhke.step3_alice(p.c_, blinding_factor.clone(), *key)?;
+                let proof = Proof::new(p.amount, secret, pub_alice, current_keyset_id.clone());
+                Ok(match p.dleq {
+                    Some(dleq) => proof.with_dleq(ProofDleq {
+                        e: dleq.e,
+                        s: dleq.s,
+                        r: blinding_factor.to_secret_key(),
+                    }),
+                    None => proof,
+                })
             })
             .collect::<Result<Vec<_>, MokshaWalletError>>()?
             .into())
     }
 
+    /// Verifies the DLEQ proofs of all proofs that contain one, so that a token can be trusted before it is swapped at the mint.
+    /// Proofs without a DLEQ proof or from a different keyset are skipped.
+    pub fn verify_proofs_dleq(
+        &self,
+        wallet_keyset: &WalletKeyset,
+        proofs: &Proofs,
+    ) -> Result<(), MokshaWalletError> {
+        for proof in proofs.proofs() {
+            if proof.dleq.is_none() || proof.keyset_id != wallet_keyset.keyset_id.to_string() {
+                continue;
+            }
+
+            let key = wallet_keyset
+                .public_keys
+                .get(&proof.amount)
+                .ok_or(MokshaWalletError::PubkeyNotFound)?;
+            if !self.dhke.verify_proof_dleq(&proof, *key)? {
+                return Err(MokshaWalletError::InvalidDleq(proof.c.to_string()));
+            }
+        }
+        Ok(())
+    }
+
     pub async fn get_proofs(&self) -> Result<Proofs, MokshaWalletError> {
         let mut tx = self.localstore.begin_tx().await?;
         let proofs = self.localstore.get_proofs(&mut tx).await?;
@@ -853,19 +1320,28 @@ mod tests {
     use std::collections::HashMap;
 
     use crate::client::MockCashuClient;
+    use crate::error::MokshaWalletError;
     use crate::localstore::sqlite::SqliteLocalStore;
     use crate::localstore::{LocalStore, WalletKeyset};
+    use crate::secret::DeterministicSecret;
     use crate::wallet::WalletBuilder;
 
+    use moksha_core::blind::{BlindedMessage, BlindedSignature, DleqProof, TotalAmount};
+    use moksha_core::dhke::Dhke;
     use moksha_core::fixture::{read_fixture, read_fixture_as};
+    use moksha_core::htlc::HTLCConditions;
     use moksha_core::keyset::{KeysetId, Keysets, MintKeyset};
+    use moksha_core::p2pk::{P2PKConditions, SigFlag};
     use moksha_core::primitives::{
-        CurrencyUnit, KeyResponse, KeysResponse, PaymentMethod, PostMeltBolt11Response,
-        PostMeltQuoteBolt11Response, PostMintBolt11Response, PostSwapResponse,
+        CurrencyUnit, KeyResponse, KeysResponse, PaymentMethod, PostCheckStateResponse,
+        PostMeltBolt11Response, PostMeltQuoteBolt11Response, PostMintBolt11Response,
+        PostRestoreResponse, PostSwapResponse, ProofState, SpendState,
     };
 
+    use moksha_core::proof::{Proof, ProofDleq, Proofs};
+    use moksha_core::secret::WellKnownSecret;
     use moksha_core::token::TokenV3;
-    use secp256k1::PublicKey;
+    use secp256k1::{PublicKey, Secp256k1, SecretKey};
     use url::Url;
 
     fn create_mock() -> MockCashuClient {
@@ -920,6 +1396,36 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_blank_blinded_messages_count() -> anyhow::Result<()> {
+        let localstore = SqliteLocalStore::with_in_memory().await?;
+        let wallet_keyset = create_test_wallet_keyset()?;
+        let mut tx = localstore.begin_tx().await?;
+        localstore.upsert_keyset(&mut tx, &wallet_keyset).await?;
+        tx.commit().await?;
+
+        let wallet = WalletBuilder::new()
+            .with_client(create_mock())
+            .with_localstore(localstore)
+            .build()
+            .await?;
+        for (fee_reserve, count) in [
+            (1, 1),
+            (2, 1),
+            (3, 2),
+            (4, 2),
+            (5, 3),
+            (1024, 10),
+            (1025, 11),
+        ] {
+            let result = wallet
+                .create_blank(fee_reserve.into(), &KeysetId::new("00d31cecf59d18c0")?)
+                .await?;
+            assert_eq!(count, result.len(), "fee_reserve {fee_reserve}");
+        }
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_blank_blinded_messages_zero_sats() -> anyhow::Result<()> {
         let localstore = SqliteLocalStore::with_in_memory().await?;
@@ -1008,6 +1514,88 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_mint_tokens_invalid_dleq() -> anyhow::Result<()> {
+        let mut mint_response =
+            read_fixture_as::<PostMintBolt11Response>("post_mint_response_20.json")?;
+        let key = SecretKey::from_slice(&[1u8; 32])?;
+        mint_response.signatures.iter_mut().for_each(|sig| {
+            sig.dleq = Some(DleqProof { e: key, s: key });
+        });
+
+        let mut client = create_mock();
+        client
+            .expect_post_mint_bolt11()
+            .returning(move |_, _, _| Ok(mint_response.clone()));
+
+        let localstore = SqliteLocalStore::with_in_memory().await?;
+        let wallet_keyset = create_test_wallet_keyset()?;
+        let mut tx = localstore.begin_tx().await?;
+        localstore.upsert_keyset(&mut tx, &wallet_keyset).await?;
+        tx.commit().await?;
+
+        let wallet = WalletBuilder::new()
+            .with_client(client)
+            .with_localstore(localstore)
+            .build()
+            .await?;
+
+        let result = wallet
+            .mint_tokens(
+                &wallet_keyset,
+                &PaymentMethod::Bolt11,
+                20.into(),
+                "hash".to_string(),
+            )
+            .await;
+        assert!(matches!(result, Err(MokshaWalletError::InvalidDleq(_))));
+        assert_eq!(0, wallet.get_balance().await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_receive_tokens_invalid_dleq() -> anyhow::Result<()> {
+        let mut client = create_mock();
+        client.expect_post_swap().never();
+
+        let localstore = SqliteLocalStore::with_in_memory().await?;
+        let keyset = create_test_wallet_keyset()?;
+        let mut tx = localstore.begin_tx().await?;
+        localstore.upsert_keyset(&mut tx, &keyset).await?;
+        tx.commit().await?;
+
+        let wallet = WalletBuilder::new()
+            .with_client(client)
+            .with_localstore(localstore)
+            .build()
+            .await?;
+
+        let tokens: TokenV3 = read_fixture("token_64.cashu")?.try_into()?;
+        let key = SecretKey::from_slice(&[1u8; 32])?;
+        let proofs: Proofs = tokens
+            .proofs()
+            .proofs()
+            .into_iter()
+            .map(|proof| {
+                Proof {
+                    keyset_id: keyset.keyset_id.to_string(),
+                    ..proof
+                }
+                .with_dleq(ProofDleq {
+                    e: key,
+                    s: key,
+                    r: key,
+                })
+            })
+            .collect::<Vec<_>>()
+            .into();
+        let tokens: TokenV3 = (keyset.mint_url.clone(), proofs).into();
+
+        let result = wallet.receive_tokens(&keyset, &tokens).await;
+        assert!(matches!(result, Err(MokshaWalletError::InvalidDleq(_))));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_swap() -> anyhow::Result<()> {
         let split_response = read_fixture_as::<PostSwapResponse>("post_swap_response_24_40.json")?;
@@ -1042,6 +1630,421 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_swap_amount_mismatch() -> anyhow::Result<()> {
+        // the mint doesn't return a signature for every output
+        let mut split_response =
+            read_fixture_as::<PostSwapResponse>("post_swap_response_24_40.json")?;
+        split_response.signatures.pop();
+        let mut client = create_mock();
+        client
+            .expect_post_swap()
+            .returning(move |_, _, _| Ok(split_response.clone()));
+        let localstore = SqliteLocalStore::with_in_memory().await?;
+        let keyset = create_test_wallet_keyset()?;
+        let mut tx = localstore.begin_tx().await?;
+        localstore.upsert_keyset(&mut tx, &keyset).await?;
+        tx.commit().await?;
+
+        let wallet = WalletBuilder::new()
+            .with_client(client)
+            .with_localstore(localstore)
+            .build()
+            .await?;
+
+        let tokens = read_fixture("token_64.cashu")?.try_into()?;
+        let result = wallet.swap_tokens(&keyset, &tokens, 20.into()).await;
+        assert!(matches!(
+            result,
+            Err(MokshaWalletError::UnexpectedResponse(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_tokens_input_fee() -> anyhow::Result<()> {
+        let keyset = WalletKeyset {
+            input_fee_ppk: 1_000,
+            ..create_test_wallet_keyset()?
+        };
+        let mut client = create_mock();
+        // the 32 sat proof is selected for 10 sats and the mint charges 1 sat for the input
+        client
+            .expect_post_swap()
+            .withf(|_, inputs, outputs| inputs.total_amount() == 32 && outputs.total_amount() == 31)
+            .times(1)
+            .returning(|_, _, _| Ok(PostSwapResponse { signatures: vec![] }));
+
+        let proofs: Proofs = TryInto::<TokenV3>::try_into(read_fixture("token_60.cashu")?)?
+            .proofs()
+            .proofs()
+            .into_iter()
+            .map(|proof| Proof {
+                keyset_id: keyset.keyset_id.to_string(),
+                ..proof
+            })
+            .collect::<Vec<_>>()
+            .into();
+        let localstore = SqliteLocalStore::with_in_memory().await?;
+        let mut tx = localstore.begin_tx().await?;
+        localstore.upsert_keyset(&mut tx, &keyset).await?;
+        localstore.add_proofs(&mut tx, &proofs).await?;
+        tx.commit().await?;
+
+        let wallet = WalletBuilder::new()
+            .with_client(client)
+            .with_localstore(localstore)
+            .build()
+            .await?;
+        wallet.send_tokens(&keyset, 10).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_swap_locked() -> anyhow::Result<()> {
+        let split_response = read_fixture_as::<PostSwapResponse>("post_swap_response_24_40.json")?;
+        let mut client = create_mock();
+        client
+            .expect_post_swap()
+            .returning(move |_, _, _| Ok(split_response.clone()));
+        let localstore = SqliteLocalStore::with_in_memory().await?;
+        let keyset = create_test_wallet_keyset()?;
+        let mut tx = localstore.begin_tx().await?;
+        localstore.upsert_keyset(&mut tx, &keyset).await?;
+        tx.commit().await?;
+
+        let wallet = WalletBuilder::new()
+            .with_client(client)
+            .with_localstore(localstore)
+            .build()
+            .await?;
+
+        let pubkey = SecretKey::from_slice(&[1; 32])?.public_key(&Secp256k1::new());
+        let tokens = read_fixture("token_64.cashu")?.try_into()?;
+        let (first, second) = wallet
+            .swap_tokens_with_conditions(
+                &keyset,
+                &tokens,
+                20.into(),
+                Some(&P2PKConditions::new(pubkey).into()),
+                None,
+                None,
+            )
+            .await?;
+
+        for proof in first.proofs().proofs() {
+            assert!(WellKnownSecret::from_secret(&proof.secret)?.is_none());
+        }
+        for proof in second.proofs().proofs() {
+            let secret =
+                WellKnownSecret::from_secret(&proof.secret)?.expect("secret is not locked");
+            assert_eq!(pubkey, P2PKConditions::try_from(&secret)?.pubkey);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_receive_locked_tokens() -> anyhow::Result<()> {
+        let signing_key = SecretKey::from_slice(&[1; 32])?;
+        let conditions = P2PKConditions::new(signing_key.public_key(&Secp256k1::new()))
+            .with_sig_flag(SigFlag::SigAll);
+        let keyset = create_test_wallet_keyset()?;
+        let tokens: TokenV3 = read_fixture("token_64.cashu")?.try_into()?;
+        let locked_proofs = tokens
+            .proofs()
+            .proofs()
+            .into_iter()
+            .map(|proof| Proof {
+                secret: conditions.to_secret().to_string(),
+                ..proof
+            })
+            .collect::<Vec<_>>();
+        let locked_tokens: TokenV3 = (
+            keyset.mint_url.clone(),
+            CurrencyUnit::Sat,
+            locked_proofs.into(),
+        )
+            .into();
+
+        let mut client = create_mock();
+        let expected_conditions = conditions.clone();
+        client
+            .expect_post_swap()
+            .withf(move |_, proofs, outputs| {
+                let verify = |msg: &[u8], witness: Option<&String>| {
+                    expected_conditions
+                        .verify(msg, witness.map(String::as_str), 0)
+                        .is_ok()
+                };
+                proofs
+                    .proofs()
+                    .iter()
+                    .all(|proof| verify(proof.secret.as_bytes(), proof.witness.as_ref()))
+                    && outputs
+                        .iter()
+                        .all(|output| verify(&output.b_.serialize(), output.witness.as_ref()))
+            })
+            .times(1)
+            .returning(|_, _, outputs| Ok(create_swap_response(&outputs)));
+        let localstore = SqliteLocalStore::with_in_memory().await?;
+        let mut tx = localstore.begin_tx().await?;
+        localstore.upsert_keyset(&mut tx, &keyset).await?;
+        tx.commit().await?;
+
+        let wallet = WalletBuilder::new()
+            .with_client(client)
+            .with_localstore(localstore)
+            .build()
+            .await?;
+
+        wallet
+            .receive_locked_tokens(&keyset, &locked_tokens, &signing_key)
+            .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_receive_htlc_tokens() -> anyhow::Result<()> {
+        let preimage = [42u8; 32];
+        let conditions = HTLCConditions::from_preimage(&preimage);
+        let keyset = create_test_wallet_keyset()?;
+        let tokens: TokenV3 = read_fixture("token_64.cashu")?.try_into()?;
+        let locked_proofs = tokens
+            .proofs()
+            .proofs()
+            .into_iter()
+            .map(|proof| Proof {
+                secret: conditions.to_secret().to_string(),
+                ..proof
+            })
+            .collect::<Vec<_>>();
+        let locked_tokens: TokenV3 = (
+            keyset.mint_url.clone(),
+            CurrencyUnit::Sat,
+            locked_proofs.into(),
+        )
+            .into();
+
+        let mut client = create_mock();
+        let expected_conditions = conditions.clone();
+        client
+            .expect_post_swap()
+            .withf(move |_, proofs, _| {
+                proofs.proofs().iter().all(|proof| {
+                    expected_conditions
+                        .verify(proof.secret.as_bytes(), proof.witness.as_deref(), 0)
+                        .is_ok()
+                })
+            })
+            .times(1)
+            .returning(|_, _, outputs| Ok(create_swap_response(&outputs)));
+        let localstore = SqliteLocalStore::with_in_memory().await?;
+        let mut tx = localstore.begin_tx().await?;
+        localstore.upsert_keyset(&mut tx, &keyset).await?;
+        tx.commit().await?;
+
+        let wallet = WalletBuilder::new()
+            .with_client(client)
+            .with_localstore(localstore)
+            .build()
+            .await?;
+
+        wallet
+            .receive_htlc_tokens(&keyset, &locked_tokens, &hex::encode(preimage), None)
+            .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_proofs_state() -> anyhow::Result<()> {
+        let keyset = create_test_wallet_keyset()?;
+        let tokens: TokenV3 = read_fixture("token_60.cashu")?.try_into()?;
+        let proofs: Proofs = tokens
+            .proofs()
+            .proofs()
+            .into_iter()
+            .map(|proof| Proof {
+                keyset_id: keyset.keyset_id.to_string(),
+                ..proof
+            })
+            .collect::<Vec<_>>()
+            .into();
+        let spent_y = Dhke::hash_to_curve(proofs.proofs()[0].secret.as_bytes())?;
+
+        let mut client = create_mock();
+        client.expect_post_checkstate().returning(move |_, ys| {
+            Ok(PostCheckStateResponse {
+                states: ys
+                    .into_iter()
+                    .map(|y| ProofState {
+                        y,
+                        state: if y == spent_y {
+                            SpendState::Spent
+                        } else {
+                            SpendState::Unspent
+                        },
+                        witness: None,
+                    })
+                    .collect(),
+            })
+        });
+
+        let localstore = SqliteLocalStore::with_in_memory().await?;
+        let mut tx = localstore.begin_tx().await?;
+        localstore.upsert_keyset(&mut tx, &keyset).await?;
+        localstore.add_proofs(&mut tx, &proofs).await?;
+        tx.commit().await?;
+
+        let wallet = WalletBuilder::new()
+            .with_client(client)
+            .with_localstore(localstore)
+            .build()
+            .await?;
+
+        let states = wallet.check_proofs_state(&keyset).await?;
+        assert_eq!(4, states.len());
+        assert_eq!(60 - proofs.proofs()[0].amount, wallet.get_balance().await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_restore() -> anyhow::Result<()> {
+        let seed_words =
+            "half depart obvious quality work element tank gorilla view sugar picture humble";
+        let mint_keyset = MintKeyset::new("mykey", "");
+        let keyset = WalletKeyset::new(
+            &KeysetId::new(&mint_keyset.keyset_id)?,
+            &Url::parse("http://127.0.0.1:3338")?,
+            &CurrencyUnit::Sat,
+            0,
+            mint_keyset.public_keys.clone(),
+            true,
+            0,
+        );
+
+        // the mint issued signatures for the secrets 1 (8 sat), 2 (16 sat) and 5 (2 sat). Secret 2 is already spent
+        let dhke = Dhke::new();
+        let secrets = DeterministicSecret::from_seed_words(seed_words)?.derive_range(
+            &keyset.keyset_id,
+            0,
+            6,
+        )?;
+        let issued = [(1, 8), (2, 16), (5, 2)]
+            .into_iter()
+            .map(|(index, amount)| {
+                let (secret, blinding_factor) = &secrets[index];
+                Ok((dhke.step1_alice(secret.clone(), blinding_factor)?, amount))
+            })
+            .collect::<anyhow::Result<HashMap<PublicKey, u64>>>()?;
+        let spent_y = Dhke::hash_to_curve(secrets[2].0.as_bytes())?;
+
+        let mut client = create_mock();
+        client.expect_post_restore().returning(move |_, outputs| {
+            let (outputs, signatures) = outputs
+                .into_iter()
+                .filter_map(|output| {
+                    let amount = *issued.get(&output.b_)?;
+                    let key = mint_keyset.private_keys.get(&amount)?;
+                    let (c_, dleq) = Dhke::new().step2_bob_dleq(output.b_, key).ok()?;
+                    Some((
+                        BlindedMessage { amount, ..output },
+                        BlindedSignature {
+                            amount,
+                            c_,
+                            id: mint_keyset.keyset_id.clone(),
+                            dleq: Some(dleq),
+                        },
+                    ))
+                })
+                .unzip();
+            Ok(PostRestoreResponse {
+                outputs,
+                signatures,
+            })
+        });
+        client.expect_post_checkstate().returning(move |_, ys| {
+            Ok(PostCheckStateResponse {
+                states: ys
+                    .into_iter()
+                    .map(|y| ProofState {
+                        y,
+                        state: if y == spent_y {
+                            SpendState::Spent
+                        } else {
+                            SpendState::Unspent
+                        },
+                        witness: None,
+                    })
+                    .collect(),
+            })
+        });
+
+        let localstore = SqliteLocalStore::with_in_memory().await?;
+        let mut tx = localstore.begin_tx().await?;
+        localstore.upsert_keyset(&mut tx, &keyset).await?;
+        tx.commit().await?;
+
+        let mut wallet = WalletBuilder::new()
+            .with_client(client)
+            .with_localstore(localstore.clone())
+            .build()
+            .await?;
+
+        let restored_amount = wallet.restore(seed_words, false).await?;
+        assert_eq!(10, restored_amount);
+        assert_eq!(10, wallet.get_balance().await?);
+
+        let mut tx = localstore.begin_tx().await?;
+        assert_eq!(
+            Some(seed_words.to_owned()),
+            localstore.get_seed(&mut tx).await?
+        );
+        let keysets = localstore.get_keysets(&mut tx).await?;
+        tx.commit().await?;
+        assert_eq!(5, keysets[0].last_index);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_restore_keeps_seed_of_used_wallet() -> anyhow::Result<()> {
+        let seed_words =
+            "half depart obvious quality work element tank gorilla view sugar picture humble";
+        let fixture: TokenV3 = read_fixture("token_60.cashu")?.try_into()?;
+        let localstore = SqliteLocalStore::with_in_memory().await?;
+        let mut tx = localstore.begin_tx().await?;
+        localstore.add_proofs(&mut tx, &fixture.proofs()).await?;
+        localstore
+            .upsert_keyset(&mut tx, &create_test_wallet_keyset()?)
+            .await?;
+        tx.commit().await?;
+
+        let mut client = create_mock();
+        client
+            .expect_post_restore()
+            .returning(|_, _| Err(MokshaWalletError::MintError("unavailable".to_owned())));
+        let mut wallet = WalletBuilder::new()
+            .with_client(client)
+            .with_localstore(localstore.clone())
+            .build()
+            .await?;
+        let mut tx = localstore.begin_tx().await?;
+        let seed = localstore.get_seed(&mut tx).await?;
+        tx.commit().await?;
+
+        // the wallet already has tokens of another seed
+        let result = wallet.restore(seed_words, false).await;
+        assert!(matches!(result, Err(MokshaWalletError::SeedMismatch)));
+
+        // the seed is only replaced after the tokens are restored
+        let result = wallet.restore(seed_words, true).await;
+        assert!(matches!(result, Err(MokshaWalletError::MintError(_))));
+
+        let mut tx = localstore.begin_tx().await?;
+        assert_eq!(seed, localstore.get_seed(&mut tx).await?);
+        tx.commit().await?;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_get_balance() -> anyhow::Result<()> {
         let fixture = read_fixture("token_60.cashu")?; // 60 tokens (4,8,16,32)
@@ -1088,10 +2091,9 @@ mod tests {
             .expect_post_melt_quote_bolt11()
             .returning(move |_, _, _| Ok(quote_response.clone()));
 
-        let swap_response = read_fixture_as::<PostSwapResponse>("post_swap_response_24_40.json")?;
         mock_client
             .expect_post_swap()
-            .returning(move |_, _, _| Ok(swap_response.clone()));
+            .returning(|_, _, outputs| Ok(create_swap_response(&outputs)));
 
         let mint_url = Url::parse("http://localhost:8080/").expect("invalid url");
         let wallet = WalletBuilder::new()
@@ -1100,8 +2102,6 @@ mod tests {
             .build()
             .await?;
 
-        // FIXME fix mock response: Warning Error in swap: input 32 != output 24 + 40
-
         // 21 sats
         let invoice = "lnbcrt210n1pjg6mqhpp5pza5wzh0csjjuvfpjpv4zdjmg30vedj9ycv5tyfes9x7dp8axy0sdqqcqzzsxqyz5vqsp5vtxg4c5tw2s2zxxya2a7an0psn9mcfmlqctxzntm3sngnpyk3muq9qyyssqf8z5f90yu3wrmsufnnza25qjlnvc6ukdr094ckzn63ktcy6z5fw5mxf9skndpg2p4648gfjfvvx4qg2lqvlryyycg5k7x9h4dw70t4qq37pegm".to_string();
 
@@ -1170,6 +2170,21 @@ mod tests {
         Ok(())
     }
 
+    /// signs every output with the requested amount, the signatures are not checked without a dleq proof
+    fn create_swap_response(outputs: &[BlindedMessage]) -> PostSwapResponse {
+        PostSwapResponse {
+            signatures: outputs
+                .iter()
+                .map(|output| BlindedSignature {
+                    amount: output.amount,
+                    c_: output.b_,
+                    id: output.id.clone(),
+                    dleq: None,
+                })
+                .collect(),
+        }
+    }
+
     fn create_test_wallet_keyset() -> anyhow::Result<WalletKeyset> {
         let pub_keys = read_fixture_as::<HashMap<u64, PublicKey>>("pub_keys.json")?;
         let keyset_id = KeysetId::new("00d31cecf59d18c0")?;
@@ -1182,6 +2197,7 @@ mod tests {
             0,
             pub_keys.clone(),
             true,
+            0,
         );
         Ok(wallet_keyset)
     }